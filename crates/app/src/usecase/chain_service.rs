@@ -30,7 +30,11 @@ impl<B, C> ChainService<B, C> {
         self.repository.get_all(namespace, height).await
     }
 
-    fn revert_blob(&self, blob: &B) -> Result<TransactionHistoryResponseDTO> {
+    pub fn address_to_namespace(&self, address: &str) -> Result<C> {
+        self.repository.address_to_namespace(address)
+    }
+
+    pub fn revert_blob(&self, blob: &B) -> Result<TransactionHistoryResponseDTO> {
         self.repository.revert_blob(blob)
     }
 }
@@ -14,9 +14,29 @@ impl DatabaseService {
         self.repository.get_related_transaction(address).await
     }
 
-    pub async fn add_new_transaction(&self, height: &str, address: &str) -> Result<()> {
+    pub async fn add_new_transaction(
+        &self,
+        height: &str,
+        address: &str,
+        tx_hash: Option<&str>,
+        tx_status: Option<&str>,
+    ) -> Result<()> {
         self.repository
-            .insert_new_transaction(height, address)
+            .insert_new_transaction(height, address, tx_hash, tx_status)
             .await
     }
+
+    pub async fn fetch_transaction_by_hash(&self, tx_hash: &str) -> Result<Option<W3Transaction>> {
+        self.repository.get_transaction_by_hash(tx_hash).await
+    }
+
+    pub async fn update_transaction_status(&self, tx_hash: &str, tx_status: &str) -> Result<()> {
+        self.repository
+            .update_transaction_status(tx_hash, tx_status)
+            .await
+    }
+
+    pub async fn link_replacement(&self, tx_hash: &str, replaced_by: &str) -> Result<()> {
+        self.repository.link_replacement(tx_hash, replaced_by).await
+    }
 }
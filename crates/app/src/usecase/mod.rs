@@ -1,4 +1,5 @@
 pub mod chain_service;
 pub mod database_service;
 pub mod payment_service;
+pub mod token_service;
 pub mod web3_service;
@@ -0,0 +1,313 @@
+use anyhow::Result;
+use domain::{
+    entity::{TokenKind, W3Token},
+    repository::token_repository::TokenRepository,
+    shared::{amount::MAX_DECIMALS, dtos::TokenDTO, error::DomainError},
+};
+use std::sync::Arc;
+
+pub struct TokenService {
+    repository: Arc<dyn TokenRepository>,
+}
+
+impl TokenService {
+    pub fn new(repository: Arc<dyn TokenRepository>) -> Self {
+        Self { repository }
+    }
+
+    pub async fn list_tokens(&self) -> Result<Vec<TokenDTO>> {
+        let tokens = self.repository.list_tokens().await?;
+        Ok(tokens.into_iter().map(TokenService::to_dto).collect())
+    }
+
+    pub async fn add_token(&self, token: TokenDTO) -> Result<TokenDTO> {
+        let kind = TokenKind::from_kind(&token.kind)
+            .ok_or_else(|| DomainError::Validation(format!("Unknown token kind {}", token.kind)))?;
+        if token.symbol.trim().is_empty() || token.network.trim().is_empty() {
+            return Err(DomainError::Validation(
+                "Token symbol and network are required".to_string(),
+            )
+            .into());
+        }
+        //amounts are u128 base units, larger decimals would also overflow the USD valuation
+        if token.decimals > MAX_DECIMALS {
+            return Err(DomainError::Validation(format!(
+                "Token decimals must not exceed {}",
+                MAX_DECIMALS
+            ))
+            .into());
+        }
+        if token.transfer_fee_bps >= 10_000
+            || (!token.fee_on_transfer && token.transfer_fee_bps > 0)
+        {
+            return Err(DomainError::Validation(
+                "transfer_fee_bps must be below 10000 and only set on fee_on_transfer tokens"
+                    .to_string(),
+            )
+            .into());
+        }
+        //the minimum output of the first swap is taken from it, a missing fee would make it revert
+        if token.fee_on_transfer && token.transfer_fee_bps == 0 {
+            return Err(DomainError::Validation(
+                "fee_on_transfer tokens must be registered with their transfer_fee_bps".to_string(),
+            )
+            .into());
+        }
+        if kind == TokenKind::Native && token.fee_on_transfer {
+            return Err(DomainError::Validation(
+                "Native tokens cannot be fee_on_transfer".to_string(),
+            )
+            .into());
+        }
+        //native coins have no contract, every erc20 needs a valid contract address
+        let address = match kind {
+            TokenKind::Native => "".to_string(),
+            TokenKind::Erc20 => {
+                let address = token.address.trim().to_lowercase();
+                let hex_part = address.strip_prefix("0x").unwrap_or_default();
+                if hex_part.len() != 40 || !hex_part.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return Err(DomainError::Validation(format!(
+                        "Invalid token address {}",
+                        token.address
+                    ))
+                    .into());
+                }
+                address
+            }
+        };
+
+        let new_token = W3Token {
+            symbol: token.symbol.trim().to_uppercase(),
+            address,
+            decimals: token.decimals as i32,
+            network: token.network.trim().to_lowercase(),
+            kind: kind.map_kind(),
+            fee_on_transfer: token.fee_on_transfer,
+            transfer_fee_bps: token.transfer_fee_bps as i32,
+        };
+        if self
+            .repository
+            .get_token(&new_token.symbol, &new_token.network)
+            .await?
+            .is_some()
+        {
+            return Err(DomainError::Validation(format!(
+                "Token {} already registered on {}",
+                new_token.symbol, new_token.network
+            ))
+            .into());
+        }
+        self.repository.insert_token(&new_token).await?;
+        Ok(TokenService::to_dto(new_token))
+    }
+
+    fn to_dto(token: W3Token) -> TokenDTO {
+        TokenDTO {
+            symbol: token.symbol,
+            address: token.address,
+            decimals: token.decimals as u8,
+            network: token.network,
+            kind: token.kind,
+            fee_on_transfer: token.fee_on_transfer,
+            transfer_fee_bps: token.transfer_fee_bps.max(0) as u32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MockTokenRepository {
+        tokens: Mutex<Vec<W3Token>>,
+    }
+
+    #[async_trait]
+    impl TokenRepository for MockTokenRepository {
+        async fn list_tokens(&self) -> Result<Vec<W3Token>> {
+            Ok(self.tokens.lock().unwrap().clone())
+        }
+
+        async fn get_token(&self, symbol: &str, network: &str) -> Result<Option<W3Token>> {
+            let tokens = self.tokens.lock().unwrap();
+            Ok(tokens
+                .iter()
+                .find(|token| token.symbol == symbol && token.network == network)
+                .cloned())
+        }
+
+        async fn insert_token(&self, token: &W3Token) -> Result<()> {
+            self.tokens.lock().unwrap().push(token.clone());
+            Ok(())
+        }
+
+        async fn update_transfer_fee(
+            &self,
+            _symbol: &str,
+            _network: &str,
+            _fee_bps: i32,
+        ) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn usdc() -> TokenDTO {
+        TokenDTO {
+            symbol: " usdc ".to_string(),
+            address: "0x1C7D4B196CB0C7B01D743FBC6116A902379C7238".to_string(),
+            decimals: 6,
+            network: "Sepolia".to_string(),
+            kind: "ERC20".to_string(),
+            fee_on_transfer: false,
+            transfer_fee_bps: 0,
+        }
+    }
+
+    async fn add(token: TokenDTO) -> Result<TokenDTO> {
+        TokenService::new(Arc::new(MockTokenRepository::default()))
+            .add_token(token)
+            .await
+    }
+
+    async fn is_rejected(token: TokenDTO) -> bool {
+        matches!(
+            add(token)
+                .await
+                .map_err(|error| DomainError::from_anyhow(&error)),
+            Err(DomainError::Validation(_))
+        )
+    }
+
+    #[tokio::test]
+    async fn add_token_normalizes_and_stores_the_token() {
+        let repository = Arc::new(MockTokenRepository::default());
+        let service = TokenService::new(repository.clone());
+        let token = service.add_token(usdc()).await.unwrap();
+        assert_eq!(token.symbol, "USDC");
+        assert_eq!(token.network, "sepolia");
+        assert_eq!(token.kind, "erc20");
+        assert_eq!(token.address, "0x1c7d4b196cb0c7b01d743fbc6116a902379c7238");
+        assert_eq!(repository.list_tokens().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn add_token_rejects_unknown_kinds_and_missing_fields() {
+        assert!(
+            is_rejected(TokenDTO {
+                kind: "erc721".to_string(),
+                ..usdc()
+            })
+            .await
+        );
+        assert!(
+            is_rejected(TokenDTO {
+                symbol: " ".to_string(),
+                ..usdc()
+            })
+            .await
+        );
+        assert!(
+            is_rejected(TokenDTO {
+                network: "".to_string(),
+                ..usdc()
+            })
+            .await
+        );
+    }
+
+    #[tokio::test]
+    async fn add_token_bounds_decimals() {
+        assert!(add(TokenDTO {
+            decimals: MAX_DECIMALS,
+            ..usdc()
+        })
+        .await
+        .is_ok());
+        assert!(
+            is_rejected(TokenDTO {
+                decimals: MAX_DECIMALS + 1,
+                ..usdc()
+            })
+            .await
+        );
+    }
+
+    #[tokio::test]
+    async fn add_token_checks_transfer_fees() {
+        let taxed = TokenDTO {
+            fee_on_transfer: true,
+            transfer_fee_bps: 300,
+            ..usdc()
+        };
+        assert_eq!(add(taxed.clone()).await.unwrap().transfer_fee_bps, 300);
+        assert!(
+            is_rejected(TokenDTO {
+                transfer_fee_bps: 0,
+                ..taxed.clone()
+            })
+            .await
+        );
+        assert!(
+            is_rejected(TokenDTO {
+                transfer_fee_bps: 10_000,
+                ..taxed
+            })
+            .await
+        );
+        assert!(
+            is_rejected(TokenDTO {
+                transfer_fee_bps: 300,
+                ..usdc()
+            })
+            .await
+        );
+    }
+
+    #[tokio::test]
+    async fn add_token_checks_native_tokens_and_addresses() {
+        let eth = TokenDTO {
+            symbol: "ETH".to_string(),
+            address: "".to_string(),
+            decimals: 18,
+            kind: "native".to_string(),
+            ..usdc()
+        };
+        assert_eq!(add(eth.clone()).await.unwrap().address, "");
+        assert!(
+            is_rejected(TokenDTO {
+                fee_on_transfer: true,
+                transfer_fee_bps: 100,
+                ..eth
+            })
+            .await
+        );
+        assert!(
+            is_rejected(TokenDTO {
+                address: "0x1234".to_string(),
+                ..usdc()
+            })
+            .await
+        );
+        assert!(
+            is_rejected(TokenDTO {
+                address: format!("0x{}", "g".repeat(40)),
+                ..usdc()
+            })
+            .await
+        );
+    }
+
+    #[tokio::test]
+    async fn add_token_rejects_duplicates() {
+        let service = TokenService::new(Arc::new(MockTokenRepository::default()));
+        service.add_token(usdc()).await.unwrap();
+        let duplicate = service
+            .add_token(usdc())
+            .await
+            .map_err(|error| DomainError::from_anyhow(&error));
+        assert!(matches!(duplicate, Err(DomainError::Validation(_))));
+    }
+}
@@ -3,7 +3,19 @@ use std::sync::Arc;
 use anyhow::Result;
 use domain::{
     repository::web3_repository::Web3Repository,
-    shared::dtos::{CryptoSwapResponseDTO, CryptoTransactionResponseDTO},
+    shared::{
+        amount::TokenAmount,
+        dtos::{
+            CryptoAllowanceResponseDTO, CryptoAllowanceRevokeResponseDTO,
+            CryptoBroadcastResponseDTO, CryptoEstimateResponseDTO,
+            CryptoLiquidityPositionResponseDTO, CryptoLiquidityResponseDTO,
+            CryptoPortfolioResponseDTO, CryptoReplacementResponseDTO, CryptoSwapBuildResponseDTO,
+            CryptoSwapQuoteResponseDTO, CryptoSwapResponseDTO, CryptoTransactionBuildResponseDTO,
+            CryptoTransactionResponseDTO, CryptoTransactionStatusResponseDTO,
+            CryptoWrapResponseDTO, LiquidityOptionsDTO, SwapOptionsDTO,
+        },
+        error::DomainError,
+    },
 };
 
 pub struct Web3Service {
@@ -17,22 +29,68 @@ impl Web3Service {
 
     pub async fn transfer_token(
         &self,
-        sender_private_key: &str,
+        sender_wallet_id: &str,
         recipient_address: &str,
         amount: &str,
         chain: &str,
+        relayed: bool,
     ) -> Result<CryptoTransactionResponseDTO> {
         self.repository
-            .transfer_token(sender_private_key, recipient_address, amount, chain)
+            .transfer_token(sender_wallet_id, recipient_address, amount, chain, relayed)
             .await
     }
 
-    pub async fn get_balance(&self, signer_private_key: &str, chain: &str) -> Result<String> {
-        self.repository.get_balance(signer_private_key, chain).await
+    pub async fn get_transaction_status(
+        &self,
+        tx_hash: &str,
+    ) -> Result<CryptoTransactionStatusResponseDTO> {
+        self.repository.get_transaction_status(tx_hash).await
+    }
+
+    pub async fn speed_up_transaction(
+        &self,
+        wallet_id: &str,
+        tx_hash: &str,
+    ) -> Result<CryptoReplacementResponseDTO> {
+        self.repository
+            .speed_up_transaction(wallet_id, tx_hash)
+            .await
+    }
+
+    pub async fn cancel_transaction(
+        &self,
+        wallet_id: &str,
+        tx_hash: &str,
+    ) -> Result<CryptoReplacementResponseDTO> {
+        self.repository.cancel_transaction(wallet_id, tx_hash).await
+    }
+
+    //read paths take a public address, a custodial wallet id is only mapped to its stored address
+    pub async fn resolve_address(
+        &self,
+        wallet_id: Option<&str>,
+        address: Option<&str>,
+    ) -> Result<String> {
+        match (wallet_id, address) {
+            (None, Some(address)) => Ok(address.to_string()),
+            (Some(wallet_id), None) => self.repository.get_wallet(wallet_id).await,
+            _ => Err(DomainError::Validation(
+                "Exactly one of wallet_id or address is required".to_string(),
+            )
+            .into()),
+        }
+    }
+
+    pub async fn get_balance(&self, address: &str, chain: &str) -> Result<TokenAmount> {
+        self.repository.get_balance(address, chain).await
     }
 
-    pub async fn get_wallet(&self, signer_private_key: &str) -> Result<String> {
-        self.repository.get_wallet(signer_private_key).await
+    pub async fn get_portfolio(&self, address: &str) -> Result<CryptoPortfolioResponseDTO> {
+        self.repository.get_portfolio(address).await
+    }
+
+    pub async fn get_wallet(&self, wallet_id: &str) -> Result<String> {
+        self.repository.get_wallet(wallet_id).await
     }
 
     pub async fn create_wallet(&self) -> Result<(String, String)> {
@@ -44,10 +102,150 @@ impl Web3Service {
         from_token: &str,
         to_token: &str,
         amount: &str,
-        signer_private_key: &str,
+        wallet_id: &str,
+        options: &SwapOptionsDTO,
     ) -> Result<CryptoSwapResponseDTO> {
         self.repository
-            .swap(from_token, to_token, amount, signer_private_key)
+            .swap(from_token, to_token, amount, wallet_id, options)
+            .await
+    }
+
+    pub async fn swap_quote(
+        &self,
+        from_token: &str,
+        to_token: &str,
+        amount: &str,
+        wallet_id: Option<&str>,
+        options: &SwapOptionsDTO,
+    ) -> Result<CryptoSwapQuoteResponseDTO> {
+        self.repository
+            .swap_quote(from_token, to_token, amount, wallet_id, options)
+            .await
+    }
+
+    pub async fn estimate_transfer(
+        &self,
+        sender_wallet_id: &str,
+        recipient_address: &str,
+        amount: &str,
+        chain: &str,
+        relayed: bool,
+    ) -> Result<CryptoEstimateResponseDTO> {
+        self.repository
+            .estimate_transfer(sender_wallet_id, recipient_address, amount, chain, relayed)
+            .await
+    }
+
+    pub async fn estimate_swap(
+        &self,
+        from_token: &str,
+        to_token: &str,
+        amount: &str,
+        wallet_id: &str,
+        options: &SwapOptionsDTO,
+    ) -> Result<CryptoEstimateResponseDTO> {
+        self.repository
+            .estimate_swap(from_token, to_token, amount, wallet_id, options)
+            .await
+    }
+
+    pub async fn build_transfer(
+        &self,
+        sender_address: &str,
+        recipient_address: &str,
+        amount: &str,
+        chain: &str,
+    ) -> Result<CryptoTransactionBuildResponseDTO> {
+        self.repository
+            .build_transfer(sender_address, recipient_address, amount, chain)
+            .await
+    }
+
+    pub async fn build_swap(
+        &self,
+        from_token: &str,
+        to_token: &str,
+        amount: &str,
+        address: &str,
+        options: &SwapOptionsDTO,
+    ) -> Result<CryptoSwapBuildResponseDTO> {
+        self.repository
+            .build_swap(from_token, to_token, amount, address, options)
+            .await
+    }
+
+    pub async fn broadcast_transaction(
+        &self,
+        address: &str,
+        signed_transaction: &str,
+    ) -> Result<CryptoBroadcastResponseDTO> {
+        self.repository
+            .broadcast_transaction(address, signed_transaction)
+            .await
+    }
+
+    pub async fn wrap(&self, wallet_id: &str, amount: &str) -> Result<CryptoWrapResponseDTO> {
+        self.repository.wrap(wallet_id, amount).await
+    }
+
+    pub async fn unwrap(&self, wallet_id: &str, amount: &str) -> Result<CryptoWrapResponseDTO> {
+        self.repository.unwrap(wallet_id, amount).await
+    }
+
+    pub async fn get_allowances(
+        &self,
+        wallet_id: &str,
+        spender: Option<&str>,
+    ) -> Result<Vec<CryptoAllowanceResponseDTO>> {
+        self.repository.get_allowances(wallet_id, spender).await
+    }
+
+    pub async fn revoke_allowance(
+        &self,
+        wallet_id: &str,
+        token: &str,
+        spender: Option<&str>,
+    ) -> Result<CryptoAllowanceRevokeResponseDTO> {
+        self.repository
+            .revoke_allowance(wallet_id, token, spender)
+            .await
+    }
+
+    pub async fn add_liquidity(
+        &self,
+        wallet_id: &str,
+        token_a: &str,
+        token_b: &str,
+        amount_a: &str,
+        amount_b: &str,
+        options: &LiquidityOptionsDTO,
+    ) -> Result<CryptoLiquidityResponseDTO> {
+        self.repository
+            .add_liquidity(wallet_id, token_a, token_b, amount_a, amount_b, options)
+            .await
+    }
+
+    pub async fn remove_liquidity(
+        &self,
+        wallet_id: &str,
+        token_a: &str,
+        token_b: &str,
+        liquidity: &str,
+        options: &LiquidityOptionsDTO,
+    ) -> Result<CryptoLiquidityResponseDTO> {
+        self.repository
+            .remove_liquidity(wallet_id, token_a, token_b, liquidity, options)
+            .await
+    }
+
+    pub async fn get_liquidity_position(
+        &self,
+        wallet_id: &str,
+        token_a: &str,
+        token_b: &str,
+    ) -> Result<CryptoLiquidityPositionResponseDTO> {
+        self.repository
+            .get_liquidity_position(wallet_id, token_a, token_b)
             .await
     }
 }
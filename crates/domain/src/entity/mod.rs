@@ -1,3 +1,4 @@
+use crate::shared::{amount::TokenAmount, error::DomainError};
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
 
@@ -5,16 +6,87 @@ use sqlx::prelude::FromRow;
 pub struct W3Transaction {
     pub w3_height: String,
     pub w3_address: String,
+    //on-chain hash and last known status, none for fiat transfers
+    pub tx_hash: Option<String>,
+    pub tx_status: Option<String>,
+    //hash of the speed-up or cancel transaction sent at the same nonce
+    pub replaced_by: Option<String>,
+    //seconds since the record was written
+    pub age_seconds: i64,
+}
+
+//custodial wallet, the private key is only stored encrypted
+#[derive(Debug, Clone, FromRow, PartialEq, Eq, Serialize, Deserialize)]
+pub struct W3Wallet {
+    pub wallet_id: String,
+    pub w3_address: String,
+    pub w3_keystore: String,
+}
+
+//token supported by the service on a network
+#[derive(Debug, Clone, FromRow, PartialEq, Eq, Serialize, Deserialize)]
+pub struct W3Token {
+    pub symbol: String,
+    pub address: String,
+    pub decimals: i32,
+    pub network: String,
+    pub kind: String,
+    //taxed tokens, the receiver gets less than what was sent
+    pub fee_on_transfer: bool,
+    //last fee measured on a swap, in basis points
+    pub transfer_fee_bps: i32,
+}
+
+impl W3Token {
+    pub fn is_native(&self) -> bool {
+        TokenKind::from_kind(&self.kind) == Some(TokenKind::Native)
+    }
+
+    //parse a user amount exactly against the registered decimals
+    pub fn parse_amount(&self, amount: &str) -> Result<TokenAmount, DomainError> {
+        TokenAmount::parse(amount, self.decimals as u8)
+    }
+
+    pub fn amount_from_base_units(&self, base_units: u128) -> TokenAmount {
+        TokenAmount::from_base_units(base_units, self.decimals as u8)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Native,
+    Erc20,
+}
+
+impl TokenKind {
+    pub fn map_kind(&self) -> String {
+        match self {
+            TokenKind::Native => "native".to_string(),
+            TokenKind::Erc20 => "erc20".to_string(),
+        }
+    }
+
+    pub fn from_kind(kind: &str) -> Option<TokenKind> {
+        match kind.to_lowercase().as_str() {
+            "native" => Some(TokenKind::Native),
+            "erc20" => Some(TokenKind::Erc20),
+            _ => None,
+        }
+    }
 }
 
 pub enum Table {
     W3Transaction,
+    W3Wallet,
+    W3Token,
 }
 
 impl Table {
     pub fn map_table(&self) -> String {
         match self {
             Table::W3Transaction => "w3_transaction".to_string(),
+            Table::W3Wallet => "w3_wallet".to_string(),
+            Table::W3Token => "w3_token".to_string(),
         }
     }
 }
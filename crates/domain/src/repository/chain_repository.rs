@@ -19,4 +19,6 @@ pub trait ChainRepository<B, C> {
     async fn build_blob(&self, namespace: &str, data: Value) -> Result<B>;
 
     fn revert_blob(&self, blob: &B) -> Result<TransactionHistoryResponseDTO>;
+
+    fn address_to_namespace(&self, address: &str) -> Result<C>;
 }
@@ -5,5 +5,17 @@ use rocket::async_trait;
 pub trait DatabaseRepository: Send + Sync {
     async fn get_related_transaction(&self, address: &str) -> Result<Vec<W3Transaction>>;
 
-    async fn insert_new_transaction(&self, height: &str, address: &str) -> Result<()>;
+    async fn insert_new_transaction(
+        &self,
+        height: &str,
+        address: &str,
+        tx_hash: Option<&str>,
+        tx_status: Option<&str>,
+    ) -> Result<()>;
+
+    async fn get_transaction_by_hash(&self, tx_hash: &str) -> Result<Option<W3Transaction>>;
+
+    async fn update_transaction_status(&self, tx_hash: &str, tx_status: &str) -> Result<()>;
+
+    async fn link_replacement(&self, tx_hash: &str, replaced_by: &str) -> Result<()>;
 }
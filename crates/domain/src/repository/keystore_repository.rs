@@ -0,0 +1,10 @@
+use crate::entity::W3Wallet;
+use anyhow::Result;
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait KeystoreRepository: Send + Sync {
+    async fn insert_wallet(&self, wallet: &W3Wallet) -> Result<()>;
+
+    async fn get_wallet(&self, wallet_id: &str) -> Result<W3Wallet>;
+}
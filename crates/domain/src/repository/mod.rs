@@ -1,4 +1,7 @@
 pub mod payment_repository;
 pub mod web3_repository;
 pub mod chain_repository;
-pub mod database_repository;
\ No newline at end of file
+pub mod database_repository;
+pub mod keystore_repository;
+pub mod nonce_repository;
+pub mod token_repository;
\ No newline at end of file
@@ -0,0 +1,11 @@
+use anyhow::Result;
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait NonceRepository: Send + Sync {
+    //reserve the next nonce of an address, chain_nonce is the pending transaction count on the node
+    async fn allocate_nonce(&self, address: &str, chain_nonce: u64) -> Result<u64>;
+
+    //hand back a nonce whose transaction never reached the node
+    async fn release_nonce(&self, address: &str, nonce: u64) -> Result<()>;
+}
@@ -0,0 +1,14 @@
+use crate::entity::W3Token;
+use anyhow::Result;
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait TokenRepository: Send + Sync {
+    async fn list_tokens(&self) -> Result<Vec<W3Token>>;
+
+    async fn get_token(&self, symbol: &str, network: &str) -> Result<Option<W3Token>>;
+
+    async fn insert_token(&self, token: &W3Token) -> Result<()>;
+
+    async fn update_transfer_fee(&self, symbol: &str, network: &str, fee_bps: i32) -> Result<()>;
+}
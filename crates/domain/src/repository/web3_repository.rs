@@ -1,22 +1,58 @@
 use anyhow::Result;
 use async_trait::async_trait;
 
-use crate::shared::dtos::{CryptoSwapResponseDTO, CryptoTransactionResponseDTO};
+use crate::shared::{
+    amount::TokenAmount,
+    dtos::{
+        CryptoAllowanceResponseDTO, CryptoAllowanceRevokeResponseDTO, CryptoBroadcastResponseDTO,
+        CryptoEstimateResponseDTO, CryptoLiquidityPositionResponseDTO, CryptoLiquidityResponseDTO,
+        CryptoPortfolioResponseDTO, CryptoReplacementResponseDTO, CryptoSwapBuildResponseDTO,
+        CryptoSwapQuoteResponseDTO, CryptoSwapResponseDTO, CryptoTransactionBuildResponseDTO,
+        CryptoTransactionResponseDTO, CryptoTransactionStatusResponseDTO, CryptoWrapResponseDTO,
+        LiquidityOptionsDTO, SwapOptionsDTO,
+    },
+};
 
 #[async_trait]
 pub trait Web3Repository: Send + Sync {
     async fn transfer_token(
         &self,
-        sender_private_key: &str,
+        sender_wallet_id: &str,
         recipient_address: &str,
         amount: &str,
         chain: &str,
+        relayed: bool,
     ) -> Result<CryptoTransactionResponseDTO>;
 
-    async fn get_balance(&self, signer_private_key: &str, chain: &str) -> Result<String>;
+    //status of a sent transaction, read only
+    async fn get_transaction_status(
+        &self,
+        tx_hash: &str,
+    ) -> Result<CryptoTransactionStatusResponseDTO>;
+
+    //rebroadcast a pending transaction at the same nonce with bumped fees
+    async fn speed_up_transaction(
+        &self,
+        wallet_id: &str,
+        tx_hash: &str,
+    ) -> Result<CryptoReplacementResponseDTO>;
+
+    //replace a pending transaction with a zero-value self-transfer at the same nonce
+    async fn cancel_transaction(
+        &self,
+        wallet_id: &str,
+        tx_hash: &str,
+    ) -> Result<CryptoReplacementResponseDTO>;
+
+    //read only, any public address works and no key is involved
+    async fn get_balance(&self, address: &str, chain: &str) -> Result<TokenAmount>;
 
-    async fn get_wallet(&self, signer_private_key: &str) -> Result<String>;
+    //native and registered token balances of an address in one round trip
+    async fn get_portfolio(&self, address: &str) -> Result<CryptoPortfolioResponseDTO>;
 
+    async fn get_wallet(&self, wallet_id: &str) -> Result<String>;
+
+    //returns the new wallet id and its address
     async fn create_wallet(&self) -> Result<(String, String)>;
 
     async fn swap(
@@ -24,6 +60,112 @@ pub trait Web3Repository: Send + Sync {
         from_token: &str,
         to_token: &str,
         amount: &str,
-        signer_private_key: &str,
+        wallet_id: &str,
+        options: &SwapOptionsDTO,
     ) -> Result<CryptoSwapResponseDTO>;
+
+    //read only, nothing is signed or sent
+    async fn swap_quote(
+        &self,
+        from_token: &str,
+        to_token: &str,
+        amount: &str,
+        wallet_id: Option<&str>,
+        options: &SwapOptionsDTO,
+    ) -> Result<CryptoSwapQuoteResponseDTO>;
+
+    //gas and EIP-1559 fees of a transfer, nothing is signed or sent
+    async fn estimate_transfer(
+        &self,
+        sender_wallet_id: &str,
+        recipient_address: &str,
+        amount: &str,
+        chain: &str,
+        relayed: bool,
+    ) -> Result<CryptoEstimateResponseDTO>;
+
+    //gas and EIP-1559 fees of a swap, its approval included
+    async fn estimate_swap(
+        &self,
+        from_token: &str,
+        to_token: &str,
+        amount: &str,
+        wallet_id: &str,
+        options: &SwapOptionsDTO,
+    ) -> Result<CryptoEstimateResponseDTO>;
+
+    //unsigned transfer for an address signing with its own key, nothing is sent
+    async fn build_transfer(
+        &self,
+        sender_address: &str,
+        recipient_address: &str,
+        amount: &str,
+        chain: &str,
+    ) -> Result<CryptoTransactionBuildResponseDTO>;
+
+    //unsigned swap for an address signing with its own key, preceded by its approval when needed
+    async fn build_swap(
+        &self,
+        from_token: &str,
+        to_token: &str,
+        amount: &str,
+        address: &str,
+        options: &SwapOptionsDTO,
+    ) -> Result<CryptoSwapBuildResponseDTO>;
+
+    //send a transaction signed by the client once its sender and calldata are checked
+    async fn broadcast_transaction(
+        &self,
+        address: &str,
+        signed_transaction: &str,
+    ) -> Result<CryptoBroadcastResponseDTO>;
+
+    //native ETH -> WETH
+    async fn wrap(&self, wallet_id: &str, amount: &str) -> Result<CryptoWrapResponseDTO>;
+
+    //WETH -> native ETH
+    async fn unwrap(&self, wallet_id: &str, amount: &str) -> Result<CryptoWrapResponseDTO>;
+
+    //allowances given by the wallet on every registered erc20 token
+    async fn get_allowances(
+        &self,
+        wallet_id: &str,
+        spender: Option<&str>,
+    ) -> Result<Vec<CryptoAllowanceResponseDTO>>;
+
+    async fn revoke_allowance(
+        &self,
+        wallet_id: &str,
+        token: &str,
+        spender: Option<&str>,
+    ) -> Result<CryptoAllowanceRevokeResponseDTO>;
+
+    //deposit both tokens into their pair, approving the router when needed
+    async fn add_liquidity(
+        &self,
+        wallet_id: &str,
+        token_a: &str,
+        token_b: &str,
+        amount_a: &str,
+        amount_b: &str,
+        options: &LiquidityOptionsDTO,
+    ) -> Result<CryptoLiquidityResponseDTO>;
+
+    //burn LP tokens of the pair and withdraw both tokens
+    async fn remove_liquidity(
+        &self,
+        wallet_id: &str,
+        token_a: &str,
+        token_b: &str,
+        liquidity: &str,
+        options: &LiquidityOptionsDTO,
+    ) -> Result<CryptoLiquidityResponseDTO>;
+
+    //read only, LP balance of the wallet and its share of the reserves
+    async fn get_liquidity_position(
+        &self,
+        wallet_id: &str,
+        token_a: &str,
+        token_b: &str,
+    ) -> Result<CryptoLiquidityPositionResponseDTO>;
 }
@@ -0,0 +1,184 @@
+use crate::shared::{dtos::AmountDTO, error::DomainError};
+
+//u128 holds 38 full decimal digits, a token with more decimals could not express one whole unit
+pub const MAX_DECIMALS: u8 = 38;
+
+//exact token amount kept in base units, never goes through floating point
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenAmount {
+    base_units: u128,
+    decimals: u8,
+}
+
+impl TokenAmount {
+    //parse a user decimal string, for example "10.5" USDC -> 10500000 base units
+    pub fn parse(amount: &str, decimals: u8) -> Result<TokenAmount, DomainError> {
+        let amount = amount.trim();
+        if amount.starts_with('-') {
+            return Err(DomainError::Validation(
+                "Amount must not be negative".to_string(),
+            ));
+        }
+
+        let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+        let is_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+        if whole.is_empty()
+            || !is_digits(whole)
+            || !is_digits(fraction)
+            || (amount.contains('.') && fraction.is_empty())
+        {
+            return Err(DomainError::Validation(format!(
+                "Invalid amount {}",
+                amount
+            )));
+        }
+        if fraction.len() > decimals as usize {
+            return Err(DomainError::Validation(format!(
+                "Amount {} has more than {} fractional digits",
+                amount, decimals
+            )));
+        }
+
+        //right pad the fraction so the whole string is expressed in base units
+        let padded = format!("{}{:0<width$}", whole, fraction, width = decimals as usize);
+        let base_units = padded
+            .parse::<u128>()
+            .map_err(|_| DomainError::Validation(format!("Amount {} is too large", amount)))?;
+        if base_units == 0 {
+            return Err(DomainError::Validation(
+                "Amount must be greater than zero".to_string(),
+            ));
+        }
+
+        Ok(TokenAmount {
+            base_units,
+            decimals,
+        })
+    }
+
+    pub fn from_base_units(base_units: u128, decimals: u8) -> TokenAmount {
+        TokenAmount {
+            base_units,
+            decimals,
+        }
+    }
+
+    pub fn base_units(&self) -> u128 {
+        self.base_units
+    }
+
+    pub fn decimals(&self) -> u8 {
+        self.decimals
+    }
+
+    //human readable value without trailing zeros, for example 10500000 base units of USDC -> "10.5"
+    pub fn format(&self) -> String {
+        let digits = self.base_units.to_string();
+        let decimals = self.decimals as usize;
+        if decimals == 0 {
+            return digits;
+        }
+
+        let padded = format!("{:0>width$}", digits, width = decimals + 1);
+        let (whole, fraction) = padded.split_at(padded.len() - decimals);
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.is_empty() {
+            whole.to_string()
+        } else {
+            format!("{}.{}", whole, fraction)
+        }
+    }
+
+    pub fn to_dto(&self) -> AmountDTO {
+        AmountDTO {
+            base_units: self.base_units.to_string(),
+            value: self.format(),
+            decimals: self.decimals,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reads_exact_base_units() {
+        let amount = TokenAmount::parse("10.5", 6).unwrap();
+        assert_eq!(amount.base_units(), 10_500_000);
+        assert_eq!(amount.decimals(), 6);
+
+        let amount = TokenAmount::parse("0.000000000000000001", 18).unwrap();
+        assert_eq!(amount.base_units(), 1);
+
+        let amount = TokenAmount::parse(" 42 ", 0).unwrap();
+        assert_eq!(amount.base_units(), 42);
+    }
+
+    #[test]
+    fn parse_rejects_too_many_fractional_digits() {
+        assert!(TokenAmount::parse("1.1234567", 6).is_err());
+        assert!(TokenAmount::parse("1.5", 0).is_err());
+        assert!(TokenAmount::parse("1.123456", 6).is_ok());
+    }
+
+    #[test]
+    fn parse_rejects_negative_and_zero() {
+        assert!(TokenAmount::parse("-1", 6).is_err());
+        assert!(TokenAmount::parse("-0.5", 6).is_err());
+        assert!(TokenAmount::parse("0", 6).is_err());
+        assert!(TokenAmount::parse("0.000", 6).is_err());
+    }
+
+    #[test]
+    fn parse_rejects_malformed_input() {
+        assert!(TokenAmount::parse("1.", 6).is_err());
+        assert!(TokenAmount::parse(".5", 6).is_err());
+        assert!(TokenAmount::parse("", 6).is_err());
+        assert!(TokenAmount::parse("1e6", 6).is_err());
+        assert!(TokenAmount::parse("1.2.3", 6).is_err());
+        assert!(TokenAmount::parse("+1", 6).is_err());
+    }
+
+    #[test]
+    fn parse_rejects_u128_overflow() {
+        assert_eq!(
+            TokenAmount::parse(&u128::MAX.to_string(), 0)
+                .unwrap()
+                .base_units(),
+            u128::MAX
+        );
+        assert!(TokenAmount::parse("340282366920938463463374607431768211456", 0).is_err());
+        assert!(TokenAmount::parse("4", MAX_DECIMALS).is_err());
+        assert!(TokenAmount::parse("3", MAX_DECIMALS).is_ok());
+    }
+
+    #[test]
+    fn format_drops_trailing_zeros() {
+        assert_eq!(TokenAmount::from_base_units(10_500_000, 6).format(), "10.5");
+        assert_eq!(TokenAmount::from_base_units(1_000_000, 6).format(), "1");
+        assert_eq!(
+            TokenAmount::from_base_units(1, 18).format(),
+            "0.000000000000000001"
+        );
+        assert_eq!(TokenAmount::from_base_units(0, 6).format(), "0");
+        assert_eq!(TokenAmount::from_base_units(42, 0).format(), "42");
+    }
+
+    #[test]
+    fn parse_and_format_round_trip() {
+        for (amount, decimals) in [
+            ("10.5", 6),
+            ("1", 18),
+            ("0.000001", 6),
+            ("123456789.123456789", 18),
+            ("340282366920938463463374607431768211455", 0),
+            ("3.40282366920938463463374607431768211455", MAX_DECIMALS),
+        ] {
+            let parsed = TokenAmount::parse(amount, decimals).unwrap();
+            assert_eq!(parsed.format(), amount);
+            let reparsed = TokenAmount::parse(&parsed.format(), decimals).unwrap();
+            assert_eq!(reparsed, parsed);
+        }
+    }
+}
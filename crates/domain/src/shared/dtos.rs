@@ -6,6 +6,15 @@ pub enum TransactionType {
     Swap,
     FiatTransfer,
     CryptoTransfer,
+    Wrap,
+    Unwrap,
+    Revoke,
+    AddLiquidity,
+    RemoveLiquidity,
+    TransactionStatus,
+    SpeedUp,
+    Cancel,
+    Approve,
 }
 
 impl TransactionType {
@@ -14,6 +23,35 @@ impl TransactionType {
             TransactionType::CryptoTransfer => "CryptoTransfer".to_string(),
             TransactionType::FiatTransfer => "FiatTransfer".to_string(),
             TransactionType::Swap => "Swap".to_string(),
+            TransactionType::Wrap => "Wrap".to_string(),
+            TransactionType::Unwrap => "Unwrap".to_string(),
+            TransactionType::Revoke => "Revoke".to_string(),
+            TransactionType::AddLiquidity => "AddLiquidity".to_string(),
+            TransactionType::RemoveLiquidity => "RemoveLiquidity".to_string(),
+            TransactionType::TransactionStatus => "TransactionStatus".to_string(),
+            TransactionType::SpeedUp => "SpeedUp".to_string(),
+            TransactionType::Cancel => "Cancel".to_string(),
+            TransactionType::Approve => "Approve".to_string(),
+        }
+    }
+}
+
+//lifecycle of a sent transaction as seen from the node
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionStatus {
+    Pending,
+    Mined,
+    Failed,
+    Dropped,
+}
+
+impl TransactionStatus {
+    pub fn map_status(&self) -> String {
+        match self {
+            TransactionStatus::Pending => "pending".to_string(),
+            TransactionStatus::Mined => "mined".to_string(),
+            TransactionStatus::Failed => "failed".to_string(),
+            TransactionStatus::Dropped => "dropped".to_string(),
         }
     }
 }
@@ -22,10 +60,21 @@ impl TransactionType {
 #[serde(crate = "rocket::serde")]
 pub struct GeneralResponseDTO {
     pub status: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
     pub message: String,
     pub data: Value,
 }
 
+//Amount reported both in base units and as a human readable value
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct AmountDTO {
+    pub base_units: String,
+    pub value: String,
+    pub decimals: u8,
+}
+
 //Celestia general reponse
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(crate = "rocket::serde")]
@@ -47,7 +96,7 @@ pub struct FiatTransactionRequestDTO {
 #[serde(crate = "rocket::serde")]
 pub struct FiatTransactionResponseDTO {
     pub receipient_address: String,
-    pub amount: String,
+    pub amount: AmountDTO,
     pub timestamp: String,
 }
 
@@ -55,10 +104,13 @@ pub struct FiatTransactionResponseDTO {
 #[derive(Debug, Deserialize)]
 #[serde(crate = "rocket::serde")]
 pub struct CryptoTransactionRequestDTO {
-    pub sender_private_key: String,
+    pub sender_wallet_id: String,
     pub recipient_address: String,
     pub amount: String,
     pub chain: String,
+    //the sender signs an EIP-3009 authorization and the relayer wallet pays the gas
+    #[serde(default)]
+    pub relayed: bool,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -67,7 +119,47 @@ pub struct CryptoTransactionResponseDTO {
     pub transaction_hash: String,
     pub sender_address: String,
     pub receipient_address: String,
-    pub amount: String,
+    pub amount: AmountDTO,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relayer_address: Option<String>,
+    pub timestamp: String,
+}
+
+//Crypto Transaction status, read with GET /crypto/transaction/{hash}
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CryptoTransactionStatusResponseDTO {
+    pub transaction_hash: String,
+    //pending, mined, failed or dropped
+    pub status: String,
+    pub block_number: Option<u64>,
+    pub confirmations: u64,
+    pub gas_used: Option<String>,
+    //in wei
+    pub effective_gas_price: Option<String>,
+    //only for failed transactions
+    pub revert_reason: Option<String>,
+    pub timestamp: String,
+}
+
+//Crypto Transaction replacement, POST /crypto/transaction/{hash}/speedup and /cancel
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CryptoReplacementRequestDTO {
+    //the wallet that sent the pending transaction
+    pub wallet_id: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct CryptoReplacementResponseDTO {
+    pub transaction_hash: String,
+    pub replaced_transaction_hash: String,
+    pub address: String,
+    pub nonce: u64,
+    //bumped fees, in wei
+    pub max_fee_per_gas: String,
+    pub max_priority_fee_per_gas: String,
     pub timestamp: String,
 }
 
@@ -75,21 +167,59 @@ pub struct CryptoTransactionResponseDTO {
 #[derive(Debug, Deserialize)]
 #[serde(crate = "rocket::serde")]
 pub struct CryptoBalanceRequestDTO {
-    pub signer_private_key: String,
+    //either a custodial wallet id or any public address
+    #[serde(default)]
+    pub wallet_id: Option<String>,
+    #[serde(default)]
+    pub address: Option<String>,
     pub chain: String,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(crate = "rocket::serde")]
 pub struct CryptoBalanceResponseDTO {
-    pub balance: String,
+    pub address: String,
+    pub balance: AmountDTO,
+}
+
+//Crypto Portfolio, every registered token of the network read in one multicall
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CryptoPortfolioRequestDTO {
+    //either a custodial wallet id or any public address
+    #[serde(default)]
+    pub wallet_id: Option<String>,
+    #[serde(default)]
+    pub address: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CryptoPortfolioResponseDTO {
+    pub address: String,
+    pub holdings: Vec<CryptoHoldingDTO>,
+    //sum of the valued holdings, none when USDC is not registered on the network
+    pub total_usd_value: Option<AmountDTO>,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CryptoHoldingDTO {
+    pub symbol: String,
+    //empty for the native coin
+    pub token_address: String,
+    pub kind: String,
+    pub balance: AmountDTO,
+    //balance priced in USDC on the router, none when no pool can price it
+    pub usd_value: Option<AmountDTO>,
 }
 
 //Crypto Wallet
 #[derive(Debug, Deserialize)]
 #[serde(crate = "rocket::serde")]
 pub struct CryptoWalletRequestDTO {
-    pub signer_private_key: String,
+    pub wallet_id: String,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -102,8 +232,8 @@ pub struct CryptoWalletResponseDTO {
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(crate = "rocket::serde")]
 pub struct CryptoWalletCreationResponseDTO {
+    pub wallet_id: String,
     pub address: String,
-    pub private_key: String,
 }
 
 //Crypto Swap
@@ -113,20 +243,349 @@ pub struct CryptoSwapRequestDTO {
     pub from_token: String,
     pub to_token: String,
     pub amount: String,
-    pub signer_private_key: String,
+    pub wallet_id: String,
+    #[serde(flatten)]
+    pub options: SwapOptionsDTO,
+}
+
+//Optional swap settings, the server applies its defaults and maximums
+#[derive(Debug, Deserialize, Default)]
+#[serde(crate = "rocket::serde")]
+pub struct SwapOptionsDTO {
+    //"exact_input" (default) or "exact_output", in which case amount is the output to receive
+    pub mode: Option<String>,
+    pub slippage_bps: Option<u32>,
+    pub deadline_seconds: Option<u64>,
+    pub recipient: Option<String>,
+    //approve an unlimited amount instead of the swap input when an approval is needed
+    pub approve_max: Option<bool>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(crate = "rocket::serde")]
 pub struct CryptoSwapResponseDTO {
     pub transaction_hash: String,
+    //only when the existing allowance did not cover the swap
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approval_transaction_hash: Option<String>,
     pub address: String,
-    pub amount_in: String,
+    pub mode: String,
+    pub amount_in: AmountDTO,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount_in_max: Option<AmountDTO>,
+    pub amount_out_min: AmountDTO,
+    //received amount measured on the recipient, only for fee-on-transfer swaps
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount_out: Option<AmountDTO>,
+    pub from_token: String,
+    pub to_token: String,
+    pub path: Vec<String>,
+    pub slippage_bps: u32,
+    pub deadline: String,
+    pub recipient: String,
+    pub timestamp: String,
+}
+
+//Crypto Swap quote
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CryptoSwapQuoteRequestDTO {
     pub from_token: String,
     pub to_token: String,
+    pub amount: String,
+    #[serde(default)]
+    pub wallet_id: Option<String>,
+    #[serde(flatten)]
+    pub options: SwapOptionsDTO,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct CryptoSwapQuoteResponseDTO {
+    pub from_token: String,
+    pub to_token: String,
+    pub path: Vec<String>,
+    pub swap_method: String,
+    pub mode: String,
+    pub amount_in: AmountDTO,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount_in_max: Option<AmountDTO>,
+    pub expected_amount_out: AmountDTO,
+    pub amount_out_min: AmountDTO,
+    pub slippage_bps: u32,
+    pub price_impact_bps: u32,
+    pub gas_estimate: String,
+    pub gas_cost: AmountDTO,
+    pub timestamp: String,
+}
+
+//Crypto cost estimate, the body of a transfer or of a swap
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde", untagged)]
+pub enum CryptoEstimateRequestDTO {
+    Transfer(CryptoTransactionRequestDTO),
+    Swap(CryptoSwapRequestDTO),
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CryptoEstimateResponseDTO {
+    //"transfer" or "swap"
+    pub operation: String,
+    //wallet paying the gas, the relayer for relayed transfers
+    pub address: String,
+    pub gas_estimate: String,
+    //EIP-1559 fees in wei
+    pub base_fee_per_gas: String,
+    pub max_priority_fee_per_gas: String,
+    pub max_fee_per_gas: String,
+    //ETH sent along with the transaction
+    pub value: AmountDTO,
+    //gas at the current base fee plus the suggested tip, and the value
+    pub total_cost: AmountDTO,
+    //gas at the max fee plus the value, what the node requires the wallet to hold
+    pub max_total_cost: AmountDTO,
+    pub total_cost_usd: Option<AmountDTO>,
+    pub eth_balance: AmountDTO,
+    pub sufficient_funds: bool,
     pub timestamp: String,
 }
 
+//Client-side signing, POST /crypto/transaction/build and /crypto/swap/build
+//the address keeps its own key, the service only fills the transaction in
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CryptoTransactionBuildRequestDTO {
+    pub sender_address: String,
+    pub recipient_address: String,
+    pub amount: String,
+    pub chain: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CryptoSwapBuildRequestDTO {
+    pub from_token: String,
+    pub to_token: String,
+    pub amount: String,
+    pub address: String,
+    #[serde(flatten)]
+    pub options: SwapOptionsDTO,
+}
+
+//Unsigned EIP-1559 transaction, fees and values in wei
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct CryptoUnsignedTransactionDTO {
+    pub chain_id: u64,
+    pub from: String,
+    pub to: String,
+    pub nonce: u64,
+    pub gas_limit: String,
+    pub max_fee_per_gas: String,
+    pub max_priority_fee_per_gas: String,
+    pub value: String,
+    pub data: String,
+    //0x02 || rlp of the unsigned fields
+    pub unsigned_transaction: String,
+    //keccak256 of unsigned_transaction, what the key signs
+    pub signing_hash: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct CryptoTransactionBuildResponseDTO {
+    pub transaction: CryptoUnsignedTransactionDTO,
+    pub sender_address: String,
+    pub receipient_address: String,
+    pub amount: AmountDTO,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct CryptoSwapBuildResponseDTO {
+    //only when the existing allowance does not cover the swap, broadcast it first
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approval_transaction: Option<CryptoUnsignedTransactionDTO>,
+    pub transaction: CryptoUnsignedTransactionDTO,
+    pub address: String,
+    pub mode: String,
+    pub amount_in: AmountDTO,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount_in_max: Option<AmountDTO>,
+    pub amount_out_min: AmountDTO,
+    pub from_token: String,
+    pub to_token: String,
+    pub path: Vec<String>,
+    pub slippage_bps: u32,
+    pub deadline: String,
+    pub recipient: String,
+    pub timestamp: String,
+}
+
+//Signed transaction broadcast, POST /crypto/broadcast
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CryptoBroadcastRequestDTO {
+    //the address expected to have signed it
+    pub address: String,
+    //0x prefixed signed rlp
+    pub signed_transaction: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct CryptoBroadcastResponseDTO {
+    pub transaction_hash: String,
+    pub address: String,
+    //"transfer", "approve" or "swap"
+    pub operation: String,
+    pub to: String,
+    pub nonce: u64,
+    pub timestamp: String,
+}
+
+//Crypto Wrap / Unwrap between native ETH and WETH
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CryptoWrapRequestDTO {
+    pub wallet_id: String,
+    pub amount: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct CryptoWrapResponseDTO {
+    pub transaction_hash: String,
+    pub address: String,
+    pub amount: AmountDTO,
+    pub from_token: String,
+    pub to_token: String,
+    pub timestamp: String,
+}
+
+//Crypto Allowances, the spender defaults to the ROUTER02 contract
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CryptoAllowanceRequestDTO {
+    pub wallet_id: String,
+    #[serde(default)]
+    pub spender: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CryptoAllowanceResponseDTO {
+    pub token: String,
+    pub token_address: String,
+    pub spender: String,
+    //none when the allowance is unlimited
+    pub allowance: Option<AmountDTO>,
+    pub unlimited: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CryptoAllowanceRevokeRequestDTO {
+    pub wallet_id: String,
+    pub token: String,
+    #[serde(default)]
+    pub spender: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct CryptoAllowanceRevokeResponseDTO {
+    pub transaction_hash: String,
+    pub address: String,
+    pub token: String,
+    pub spender: String,
+    pub timestamp: String,
+}
+
+//Crypto Liquidity on the Uniswap V2 pair of two registered tokens
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CryptoLiquidityAddRequestDTO {
+    pub wallet_id: String,
+    pub token_a: String,
+    pub token_b: String,
+    //desired deposits, the router keeps the pool ratio and refunds nothing it does not pull
+    pub amount_a: String,
+    pub amount_b: String,
+    #[serde(flatten)]
+    pub options: LiquidityOptionsDTO,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CryptoLiquidityRemoveRequestDTO {
+    pub wallet_id: String,
+    pub token_a: String,
+    pub token_b: String,
+    //amount of LP tokens to burn
+    pub liquidity: String,
+    #[serde(flatten)]
+    pub options: LiquidityOptionsDTO,
+}
+
+//Optional liquidity settings, checked against the same maximums as swaps
+#[derive(Debug, Deserialize, Default)]
+#[serde(crate = "rocket::serde")]
+pub struct LiquidityOptionsDTO {
+    pub slippage_bps: Option<u32>,
+    pub deadline_seconds: Option<u64>,
+    pub approve_max: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct CryptoLiquidityResponseDTO {
+    pub transaction_hash: String,
+    //approvals sent before the router call, empty when the allowances already covered it
+    pub approval_transaction_hashes: Vec<String>,
+    pub address: String,
+    pub pair_address: String,
+    pub token_a: String,
+    pub token_b: String,
+    //amounts deposited into or withdrawn from the pair
+    pub amount_a: AmountDTO,
+    pub amount_b: AmountDTO,
+    pub amount_a_min: AmountDTO,
+    pub amount_b_min: AmountDTO,
+    //LP tokens minted or burned
+    pub liquidity: AmountDTO,
+    pub lp_balance: AmountDTO,
+    pub slippage_bps: u32,
+    pub deadline: String,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CryptoLiquidityPositionRequestDTO {
+    pub wallet_id: String,
+    pub token_a: String,
+    pub token_b: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CryptoLiquidityPositionResponseDTO {
+    pub address: String,
+    pub pair_address: String,
+    pub token_a: String,
+    pub token_b: String,
+    pub lp_balance: AmountDTO,
+    pub total_supply: AmountDTO,
+    //share of the pool owned by the wallet
+    pub share_bps: u32,
+    //underlying amounts the LP balance would withdraw at the current reserves
+    pub amount_a: AmountDTO,
+    pub amount_b: AmountDTO,
+}
+
 //Transaction history
 #[derive(Debug, Deserialize)]
 #[serde(crate = "rocket::serde")]
@@ -140,3 +599,19 @@ pub struct TransactionHistoryResponseDTO {
     pub tx_type: String,
     pub data: Value,
 }
+
+//Token registry
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct TokenDTO {
+    pub symbol: String,
+    #[serde(default)]
+    pub address: String,
+    pub decimals: u8,
+    pub network: String,
+    pub kind: String,
+    #[serde(default)]
+    pub fee_on_transfer: bool,
+    #[serde(default)]
+    pub transfer_fee_bps: u32,
+}
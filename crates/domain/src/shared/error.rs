@@ -0,0 +1,60 @@
+use thiserror::Error;
+
+//errors surfaced to the client, each one has a stable code and http status
+#[derive(Debug, Clone, Error)]
+pub enum DomainError {
+    #[error("{0}")]
+    Validation(String),
+    #[error("{0}")]
+    Upstream(String),
+    #[error("{0}")]
+    InsufficientFunds(String),
+    //the transaction reverted when simulated, nothing was sent
+    #[error("{0}")]
+    Reverted(String),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Unauthorized(String),
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl DomainError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            DomainError::Validation(_) => "VALIDATION_ERROR",
+            DomainError::Upstream(_) => "UPSTREAM_ERROR",
+            DomainError::InsufficientFunds(_) => "INSUFFICIENT_FUNDS",
+            DomainError::Reverted(_) => "EXECUTION_REVERTED",
+            DomainError::NotFound(_) => "NOT_FOUND",
+            DomainError::Unauthorized(_) => "UNAUTHORIZED",
+            DomainError::Internal(_) => "INTERNAL_ERROR",
+        }
+    }
+
+    pub fn status(&self) -> u16 {
+        match self {
+            DomainError::Validation(_) => 400,
+            DomainError::Upstream(_) => 502,
+            DomainError::InsufficientFunds(_) => 422,
+            DomainError::Reverted(_) => 422,
+            DomainError::NotFound(_) => 404,
+            DomainError::Unauthorized(_) => 401,
+            DomainError::Internal(_) => 500,
+        }
+    }
+
+    //find the domain error inside an anyhow chain, anything unknown is treated as internal
+    //database, node and http client messages stay in the server logs, the client gets a generic one
+    pub fn from_anyhow(error: &anyhow::Error) -> DomainError {
+        error
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<DomainError>())
+            .cloned()
+            .unwrap_or_else(|| {
+                tracing::error!("internal error : {:?}", error);
+                DomainError::Internal("Internal error".to_string())
+            })
+    }
+}
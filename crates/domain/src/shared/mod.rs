@@ -1 +1,3 @@
+pub mod amount;
 pub mod dtos;
+pub mod error;
@@ -1,28 +1,70 @@
 use crate::route::{
-    crypto_balance, crypto_swap, crypto_transaction, crypto_wallet, crypto_wallet_creation,
-    fiat_transaction, transaction_history,
+    admin_token_creation, admin_tokens, crypto_allowance_revoke, crypto_allowances, crypto_balance,
+    crypto_broadcast, crypto_estimate, crypto_liquidity, crypto_liquidity_add,
+    crypto_liquidity_remove, crypto_portfolio, crypto_swap, crypto_swap_build, crypto_swap_quote,
+    crypto_transaction, crypto_transaction_build, crypto_transaction_cancel,
+    crypto_transaction_speedup, crypto_transaction_status, crypto_unwrap, crypto_wallet,
+    crypto_wallet_creation, crypto_wrap, fiat_transaction, transaction_history,
 };
-use lambda_http::{Body, Error, IntoResponse, Request, Response};
+use crate::{helper::get_failed_response, state::AppState};
+use domain::shared::error::DomainError;
+use lambda_http::{Body, Error, Request, Response};
 
-pub async fn lambda_handler(event: Request) -> Result<impl IntoResponse, Error> {
-    let path = event.uri().path();
-    let method = event.method().as_str();
-    match (method, path) {
+//shared dispatcher for both the lambda function and the local rocket server
+pub async fn lambda_handler<B, C>(
+    state: &AppState<B, C>,
+    event: Request,
+) -> Result<Response<Body>, Error> {
+    let path = event.uri().path().to_string();
+    let method = event.method().to_string();
+    let result = match (method.as_str(), path.as_str()) {
         //GET
-        ("GET", "/crypto/balance") => crypto_balance(event).await,
-        ("GET", "/crypto/wallet") => crypto_wallet(event).await,
-        ("GET", "/history/transaction") => transaction_history(event).await,
+        ("GET", "/crypto/balance") => crypto_balance(state, event).await,
+        ("GET", "/crypto/portfolio") => crypto_portfolio(state, event).await,
+        ("GET", "/crypto/wallet") => crypto_wallet(state, event).await,
+        ("GET", "/crypto/allowances") => crypto_allowances(state, event).await,
+        ("GET", "/crypto/liquidity") => crypto_liquidity(state, event).await,
+        ("GET", "/history/transaction") => transaction_history(state, event).await,
+        ("GET", "/admin/tokens") => admin_tokens(state, event).await,
+        ("GET", path) if path.starts_with("/crypto/transaction/") => {
+            crypto_transaction_status(state, event).await
+        }
 
         //POST
-        ("POST", "/fiat/transaction") => fiat_transaction(event).await,
-        ("POST", "/crypto/transaction") => crypto_transaction(event).await,
-        ("POST", "/crypto/wallet") => crypto_wallet_creation(event).await,
-        ("POST", "/crypto/swap") => crypto_swap(event).await,
+        ("POST", "/fiat/transaction") => fiat_transaction(state, event).await,
+        ("POST", "/crypto/transaction") => crypto_transaction(state, event).await,
+        ("POST", "/crypto/wallet") => crypto_wallet_creation(state, event).await,
+        ("POST", "/crypto/swap") => crypto_swap(state, event).await,
+        ("POST", "/crypto/swap/quote") => crypto_swap_quote(state, event).await,
+        ("POST", "/crypto/swap/build") => crypto_swap_build(state, event).await,
+        ("POST", "/crypto/transaction/build") => crypto_transaction_build(state, event).await,
+        ("POST", "/crypto/broadcast") => crypto_broadcast(state, event).await,
+        ("POST", "/crypto/estimate") => crypto_estimate(state, event).await,
+        ("POST", "/crypto/wrap") => crypto_wrap(state, event).await,
+        ("POST", "/crypto/unwrap") => crypto_unwrap(state, event).await,
+        ("POST", "/crypto/allowances/revoke") => crypto_allowance_revoke(state, event).await,
+        ("POST", "/crypto/liquidity/add") => crypto_liquidity_add(state, event).await,
+        ("POST", "/crypto/liquidity/remove") => crypto_liquidity_remove(state, event).await,
+        ("POST", "/admin/tokens") => admin_token_creation(state, event).await,
+        ("POST", path)
+            if path.starts_with("/crypto/transaction/") && path.ends_with("/speedup") =>
+        {
+            crypto_transaction_speedup(state, event).await
+        }
+        ("POST", path) if path.starts_with("/crypto/transaction/") && path.ends_with("/cancel") => {
+            crypto_transaction_cancel(state, event).await
+        }
 
         //Out of scope
-        _ => Ok(Response::builder()
-            .status(404)
-            .body(Body::Text("Not found".to_string()))
-            .unwrap()),
+        _ => Err(DomainError::NotFound("Not found".to_string()).into()),
+    };
+
+    //every failure is answered with the error envelope instead of failing the invocation
+    match result {
+        Ok(response) => Ok(response),
+        Err(error) => {
+            tracing::warn!("{} {} failed : {:?}", method, path, error);
+            Ok(get_failed_response(&error))
+        }
     }
 }
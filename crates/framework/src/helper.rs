@@ -1,102 +1,239 @@
-use app::usecase::{chain_service::ChainService, database_service::DatabaseService};
-use domain::shared::dtos::{CelestiaSubmitModel, GeneralResponseDTO, TransactionType};
-use infra::{celestia_repository::CelestiaRepository, postgres_repository::PostgresRepository};
-use lambda_http::{Body, Response};
+use crate::state::AppState;
+use anyhow::Result;
+use domain::shared::{
+    dtos::{CelestiaSubmitModel, GeneralResponseDTO, TransactionStatus, TransactionType},
+    error::DomainError,
+};
+use lambda_http::{
+    http::{header::CONTENT_TYPE, HeaderValue, StatusCode},
+    Body, Request, Response,
+};
+use serde::{de::DeserializeOwned, Serialize};
 use serde_json::Value;
-use std::sync::Arc;
+use std::env;
+use subtle::ConstantTimeEq;
 
-pub fn get_success_response(data: String) -> Response<Body> {
-    let general_response = GeneralResponseDTO {
-        status: 200,
-        message: "success".to_string(),
-        data: serde_json::from_str(&data).unwrap(),
-    };
+//build the final http response without any fallible builder step
+fn build_response(general_response: &GeneralResponseDTO) -> Response<Body> {
+    let body = serde_json::to_string(general_response).unwrap_or_default();
+    let mut response = Response::new(Body::Text(body));
+    *response.status_mut() = StatusCode::from_u16(general_response.status as u16)
+        .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
 
-    Response::builder()
-        .status(200)
-        .header("Content-Type", "application/json")
-        .body(Body::Text(
-            serde_json::to_string(&general_response).unwrap(),
-        ))
-        .unwrap()
+//parse the json body of the request, a malformed body is a validation error
+pub fn parse_body<T: DeserializeOwned>(event: &Request) -> Result<T> {
+    let body = event.body();
+    let body_str = String::from_utf8(body.as_ref().to_vec())
+        .map_err(|_| DomainError::Validation("Request body must be utf-8".to_string()))?;
+    let request = serde_json::from_str::<T>(&body_str)
+        .map_err(|e| DomainError::Validation(format!("Invalid request body: {}", e)))?;
+    Ok(request)
 }
 
-pub fn get_failed_response(data: String, message: &str) -> Response<Body> {
-    let general_response = GeneralResponseDTO {
-        status: 400,
-        message: message.to_string(),
-        data: serde_json::from_str(&data).unwrap(),
-    };
+//read the parameter embedded in a path like /crypto/transaction/{hash}/speedup
+pub fn path_param(event: &Request, prefix: &str, suffix: &str) -> Result<String> {
+    let param = event
+        .uri()
+        .path()
+        .strip_prefix(prefix)
+        .and_then(|path| path.strip_suffix(suffix))
+        .unwrap_or_default()
+        .trim_matches('/');
+    if param.is_empty() || param.contains('/') {
+        return Err(DomainError::NotFound("Not found".to_string()).into());
+    }
+    Ok(param.to_string())
+}
+
+//admin endpoints need the x-admin-key header to match the configured admin api key,
+//compared in constant time so the key can't be guessed byte by byte from response timings
+pub fn require_admin(event: &Request) -> Result<()> {
+    let admin_key = env::var("ADMIN_API_KEY").unwrap_or_default();
+    let provided_key = event
+        .headers()
+        .get("x-admin-key")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let matches: bool = provided_key.as_bytes().ct_eq(admin_key.as_bytes()).into();
+    if admin_key.is_empty() || !matches {
+        return Err(DomainError::Unauthorized("Invalid admin key".to_string()).into());
+    }
+    Ok(())
+}
 
-    Response::builder()
-        .status(400)
-        .body(Body::Text(
-            serde_json::to_string(&general_response).unwrap(),
-        ))
-        .unwrap()
+pub fn get_success_response<T: Serialize>(data: &T) -> Response<Body> {
+    match serde_json::to_value(data) {
+        Ok(data) => build_response(&GeneralResponseDTO {
+            status: 200,
+            code: None,
+            message: "success".to_string(),
+            data,
+        }),
+        //not a domain error, so only the generic message reaches the client
+        Err(e) => get_failed_response(&e.into()),
+    }
+}
+
+pub fn get_failed_response(error: &anyhow::Error) -> Response<Body> {
+    let domain_error = DomainError::from_anyhow(error);
+    build_response(&GeneralResponseDTO {
+        status: domain_error.status() as i32,
+        code: Some(domain_error.code().to_string()),
+        message: domain_error.to_string(),
+        data: Value::Null,
+    })
 }
 
 //process if reponse is belong to transaction
-pub async fn process_success_response(
+pub async fn process_success_response<B, C>(
+    state: &AppState<B, C>,
     data: Value,
     tx_type: TransactionType,
     user_address: &str,
 ) -> Response<Body> {
-    //build general response
-    let general_response = GeneralResponseDTO {
-        status: 200,
-        message: "success".to_string(),
-        data: data.clone(),
+    //the transaction is already sent at this point, so a failed history record must not fail the request
+    if let Err(error) = record_transaction(state, data.clone(), &tx_type, user_address).await {
+        tracing::error!(
+            "failed to record {} transaction of {} : {:?}",
+            tx_type.map_tx_type(),
+            user_address,
+            error
+        );
+    }
+
+    let message = match state.dry_run {
+        true => "success (dry run, nothing was broadcast)",
+        false => "success",
     };
+    build_response(&GeneralResponseDTO {
+        status: 200,
+        code: None,
+        message: message.to_string(),
+        data,
+    })
+}
 
-    //submit the response into celestia node
-    let celestia_repository = Arc::new(CelestiaRepository::new().await);
-    let chain_service = ChainService::new(celestia_repository.clone());
+//submit the response into celestia node, then store its height in database for faster retrieving
+async fn record_transaction<B, C>(
+    state: &AppState<B, C>,
+    data: Value,
+    tx_type: &TransactionType,
+    user_address: &str,
+) -> Result<()> {
+    //on-chain transactions start as pending, their status is refreshed when it is queried
+    let tx_hash = data
+        .get("transaction_hash")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let tx_status = tx_hash
+        .as_ref()
+        .map(|_| TransactionStatus::Pending.map_status());
+    let replaced_hash = data
+        .get("replaced_transaction_hash")
+        .and_then(Value::as_str)
+        .map(str::to_string);
     let celestia_submit_model = CelestiaSubmitModel {
-        tx_type: TransactionType::map_tx_type(&tx_type),
-        data: data.clone(),
+        tx_type: TransactionType::map_tx_type(tx_type),
+        data,
     };
-    let blob = chain_service
-        .build_blob(
+    let blob = state
+        .chain_service
+        .build_blob(user_address, serde_json::to_value(celestia_submit_model)?)
+        .await?;
+    let height = state.chain_service.submit(&[blob]).await?;
+
+    //a dry-run hash never exists on chain, so it is neither indexed nor linked to what it replaces
+    if state.dry_run {
+        return Ok(());
+    }
+    state
+        .database_service
+        .add_new_transaction(
+            height.to_string().as_str(),
             user_address,
-            serde_json::to_value(celestia_submit_model).unwrap(),
+            tx_hash.as_deref(),
+            tx_status.as_deref(),
         )
-        .await
-        .unwrap();
-    let height = chain_service.submit(&[blob]).await.unwrap();
-    //get again to check
-    //store it in database for faster retrieving
-    let db_repository = Arc::new(PostgresRepository::new().await);
-    let db_service = DatabaseService::new(db_repository.clone());
-    let rs = db_service
-        .add_new_transaction(height.to_string().as_str(), user_address)
-        .await
-        .unwrap();
+        .await?;
 
-    Response::builder()
-        .status(200)
-        .header("Content-Type", "application/json")
-        .body(Body::Text(
-            serde_json::to_string(&general_response).unwrap(),
-        ))
-        .unwrap()
+    //a speed-up or cancel points the record of the transaction it replaces at itself
+    if let (Some(replaced_hash), Some(tx_hash)) = (replaced_hash, tx_hash) {
+        state
+            .database_service
+            .link_replacement(&replaced_hash, &tx_hash)
+            .await?;
+    }
+    Ok(())
 }
 
-pub fn process_failed_response(
-    data: String,
-    message: &str,
-    tx_type: TransactionType,
+//answer a status query, the history record is only touched when the status has changed
+pub async fn process_status_response<B, C>(
+    state: &AppState<B, C>,
+    data: Value,
+    tx_hash: &str,
+    tx_status: &str,
 ) -> Response<Body> {
-    let general_response = GeneralResponseDTO {
-        status: 400,
-        message: message.to_string(),
-        data: serde_json::from_str(&data).unwrap(),
+    //the status is read from chain, so a failed history update must not fail the request
+    if let Err(error) = record_status(state, data.clone(), tx_hash, tx_status).await {
+        tracing::error!(
+            "failed to record status {} of transaction {} : {:?}",
+            tx_status,
+            tx_hash,
+            error
+        );
+    }
+
+    build_response(&GeneralResponseDTO {
+        status: 200,
+        code: None,
+        message: "success".to_string(),
+        data,
+    })
+}
+
+//celestia blobs cannot be edited, so the new status is appended as its own blob under the same address
+async fn record_status<B, C>(
+    state: &AppState<B, C>,
+    data: Value,
+    tx_hash: &str,
+    tx_status: &str,
+) -> Result<()> {
+    let record = match state
+        .database_service
+        .fetch_transaction_by_hash(tx_hash)
+        .await?
+    {
+        Some(record) => record,
+        None => return Ok(()),
     };
+    if record.tx_status.as_deref() == Some(tx_status) {
+        return Ok(());
+    }
 
-    Response::builder()
-        .status(400)
-        .body(Body::Text(
-            serde_json::to_string(&general_response).unwrap(),
-        ))
-        .unwrap()
+    let celestia_submit_model = CelestiaSubmitModel {
+        tx_type: TransactionType::map_tx_type(&TransactionType::TransactionStatus),
+        data,
+    };
+    let blob = state
+        .chain_service
+        .build_blob(
+            &record.w3_address,
+            serde_json::to_value(celestia_submit_model)?,
+        )
+        .await?;
+    let height = state.chain_service.submit(&[blob]).await?;
+    state
+        .database_service
+        .add_new_transaction(height.to_string().as_str(), &record.w3_address, None, None)
+        .await?;
+    state
+        .database_service
+        .update_transaction_status(tx_hash, tx_status)
+        .await?;
+    Ok(())
 }
@@ -1,3 +1,5 @@
 pub mod handler;
 pub mod helper;
 pub mod route;
+pub mod server;
+pub mod state;
@@ -1,231 +1,550 @@
 use crate::helper::{
-    get_failed_response, get_success_response, process_failed_response, process_success_response,
-};
-use app::{
-    self,
-    usecase::{
-        chain_service::ChainService, database_service::DatabaseService,
-        payment_service::PaymentService, web3_service::Web3Service,
-    },
+    get_success_response, parse_body, path_param, process_status_response,
+    process_success_response, require_admin,
 };
+use crate::state::AppState;
+use anyhow::Result;
 use domain::{
     self,
-    shared::dtos::{
-        CryptoBalanceRequestDTO, CryptoBalanceResponseDTO, CryptoSwapRequestDTO,
-        CryptoTransactionRequestDTO, CryptoWalletCreationResponseDTO, CryptoWalletRequestDTO,
-        CryptoWalletResponseDTO, FiatTransactionRequestDTO, TransactionHistoryRequestDTO,
-        TransactionHistoryResponseDTO, TransactionType,
+    shared::{
+        dtos::{
+            CryptoAllowanceRequestDTO, CryptoAllowanceRevokeRequestDTO, CryptoBalanceRequestDTO,
+            CryptoBalanceResponseDTO, CryptoBroadcastRequestDTO, CryptoEstimateRequestDTO,
+            CryptoLiquidityAddRequestDTO, CryptoLiquidityPositionRequestDTO,
+            CryptoLiquidityRemoveRequestDTO, CryptoPortfolioRequestDTO,
+            CryptoReplacementRequestDTO, CryptoSwapBuildRequestDTO, CryptoSwapQuoteRequestDTO,
+            CryptoSwapRequestDTO, CryptoTransactionBuildRequestDTO, CryptoTransactionRequestDTO,
+            CryptoWalletCreationResponseDTO, CryptoWalletRequestDTO, CryptoWalletResponseDTO,
+            CryptoWrapRequestDTO, FiatTransactionRequestDTO, TokenDTO,
+            TransactionHistoryRequestDTO, TransactionHistoryResponseDTO, TransactionStatus,
+            TransactionType,
+        },
+        error::DomainError,
     },
 };
-use infra::{
-    self, celestia_repository::CelestiaRepository, circle_repository::CircleRepository,
-    infura_repository::InfuraRepository, postgres_repository::PostgresRepository,
-};
-use lambda_http::{aws_lambda_events::encodings::Error, Body, Request, Response};
-use rocket::serde::json::to_value;
-use serde_json::to_string;
-use std::sync::Arc;
+use lambda_http::{Body, Request, Response};
+use serde_json::to_value;
 
-//transaction to transfer fiat to users wallet after banking payment
-pub async fn fiat_transaction(event: Request) -> Result<Response<Body>, Error> {
-    let repository = Arc::new(CircleRepository::new());
-    let payment_service = PaymentService::new(repository);
+//a transaction the node does not know yet may still be on its way to the pool
+const DROPPED_GRACE_SECONDS: i64 = 600;
 
-    let body = event.body();
-    let body_str = String::from_utf8(body.as_ref().to_vec())?;
-    let fiat_transaction_request: FiatTransactionRequestDTO = serde_json::from_str(&body_str)?;
+//transaction to transfer fiat to users wallet after banking payment
+pub async fn fiat_transaction<B, C>(
+    state: &AppState<B, C>,
+    event: Request,
+) -> Result<Response<Body>> {
+    let fiat_transaction_request: FiatTransactionRequestDTO = parse_body(&event)?;
 
-    match payment_service
+    let response = state
+        .payment_service
         .process_fiat(
             &fiat_transaction_request.amount,
             &fiat_transaction_request.chain,
             &fiat_transaction_request.destination_address,
         )
-        .await
-    {
-        Ok(response) => {
-            let json_value = to_value(response.clone()).unwrap();
-            let final_response = process_success_response(
-                json_value,
-                TransactionType::FiatTransfer,
-                &response.clone().receipient_address,
-            )
-            .await;
-            Ok(final_response)
-        }
-        Err(error) => Ok(process_failed_response(
-            error.to_string(),
-            "Failed",
-            TransactionType::FiatTransfer,
-        )),
-    }
+        .await?;
+    let json_value = to_value(response.clone())?;
+    Ok(process_success_response(
+        state,
+        json_value,
+        TransactionType::FiatTransfer,
+        &response.receipient_address,
+    )
+    .await)
 }
 
 //transaction to transfer erc20 token between users
-pub async fn crypto_transaction(event: Request) -> Result<Response<Body>, Error> {
-    let repository = Arc::new(InfuraRepository::new());
-    let web3_service = Web3Service::new(repository);
+pub async fn crypto_transaction<B, C>(
+    state: &AppState<B, C>,
+    event: Request,
+) -> Result<Response<Body>> {
+    let crypto_transaction_request: CryptoTransactionRequestDTO = parse_body(&event)?;
 
-    let body = event.body();
-    let body_str = String::from_utf8(body.as_ref().to_vec())?;
-    let crypto_transaction_request: CryptoTransactionRequestDTO = serde_json::from_str(&body_str)?;
-
-    match web3_service
+    let response = state
+        .web3_service
         .transfer_token(
-            &crypto_transaction_request.sender_private_key,
+            &crypto_transaction_request.sender_wallet_id,
             &crypto_transaction_request.recipient_address,
             &crypto_transaction_request.amount,
             &crypto_transaction_request.chain,
+            crypto_transaction_request.relayed,
         )
-        .await
-    {
-        Ok(response) => {
-            let json_value = to_value(response.clone()).unwrap();
-            let final_response = process_success_response(
-                json_value,
-                TransactionType::CryptoTransfer,
-                &response.clone().sender_address,
-            )
-            .await;
-            Ok(final_response)
+        .await?;
+    let json_value = to_value(response.clone())?;
+    Ok(process_success_response(
+        state,
+        json_value,
+        TransactionType::CryptoTransfer,
+        &response.sender_address,
+    )
+    .await)
+}
+
+//allow client to follow an on-chain transaction until it is mined, failed or dropped
+pub async fn crypto_transaction_status<B, C>(
+    state: &AppState<B, C>,
+    event: Request,
+) -> Result<Response<Body>> {
+    let tx_hash = path_param(&event, "/crypto/transaction/", "")?;
+
+    let mut response = state.web3_service.get_transaction_status(&tx_hash).await?;
+    //the node reports unknown hashes as dropped, only a hash sent by the service can really be dropped
+    if response.status == TransactionStatus::Dropped.map_status() {
+        let record = state
+            .database_service
+            .fetch_transaction_by_hash(&tx_hash)
+            .await?
+            .ok_or_else(|| DomainError::NotFound(format!("Transaction {} not found", tx_hash)))?;
+        if record.age_seconds < DROPPED_GRACE_SECONDS {
+            response.status = TransactionStatus::Pending.map_status();
         }
-        Err(error) => Ok(process_failed_response(
-            error.to_string(),
-            "Failed",
-            TransactionType::CryptoTransfer,
-        )),
     }
+    let json_value = to_value(&response)?;
+    Ok(process_status_response(
+        state,
+        json_value,
+        &response.transaction_hash,
+        &response.status,
+    )
+    .await)
+}
+
+//allow user to rebroadcast their stuck transaction with higher fees
+pub async fn crypto_transaction_speedup<B, C>(
+    state: &AppState<B, C>,
+    event: Request,
+) -> Result<Response<Body>> {
+    let tx_hash = path_param(&event, "/crypto/transaction/", "/speedup")?;
+    let crypto_replacement_request: CryptoReplacementRequestDTO = parse_body(&event)?;
+
+    let response = state
+        .web3_service
+        .speed_up_transaction(&crypto_replacement_request.wallet_id, &tx_hash)
+        .await?;
+    let json_value = to_value(response.clone())?;
+    Ok(process_success_response(
+        state,
+        json_value,
+        TransactionType::SpeedUp,
+        &response.address,
+    )
+    .await)
+}
+
+//allow user to cancel their stuck transaction
+pub async fn crypto_transaction_cancel<B, C>(
+    state: &AppState<B, C>,
+    event: Request,
+) -> Result<Response<Body>> {
+    let tx_hash = path_param(&event, "/crypto/transaction/", "/cancel")?;
+    let crypto_replacement_request: CryptoReplacementRequestDTO = parse_body(&event)?;
+
+    let response = state
+        .web3_service
+        .cancel_transaction(&crypto_replacement_request.wallet_id, &tx_hash)
+        .await?;
+    let json_value = to_value(response.clone())?;
+    Ok(process_success_response(
+        state,
+        json_value,
+        TransactionType::Cancel,
+        &response.address,
+    )
+    .await)
 }
 
 //allow client get their balance base on chain
-pub async fn crypto_balance(event: Request) -> Result<Response<Body>, Error> {
-    let repository = Arc::new(InfuraRepository::new());
-    let web3_service = Web3Service::new(repository);
-
-    let body = event.body();
-    let body_str = String::from_utf8(body.as_ref().to_vec())?;
-    let crypto_balance_request: CryptoBalanceRequestDTO = serde_json::from_str(&body_str)?;
-
-    match web3_service
-        .get_balance(
-            &crypto_balance_request.signer_private_key,
-            &crypto_balance_request.chain,
+pub async fn crypto_balance<B, C>(
+    state: &AppState<B, C>,
+    event: Request,
+) -> Result<Response<Body>> {
+    let crypto_balance_request: CryptoBalanceRequestDTO = parse_body(&event)?;
+
+    let address = state
+        .web3_service
+        .resolve_address(
+            crypto_balance_request.wallet_id.as_deref(),
+            crypto_balance_request.address.as_deref(),
         )
-        .await
-    {
-        Ok(response) => {
-            let rs = CryptoBalanceResponseDTO { balance: response };
-            let json_str = to_string(&rs).unwrap();
-            Ok(get_success_response(json_str))
-        }
-        Err(err) => Ok(get_failed_response(err.to_string(), "Failed")),
-    }
+        .await?;
+    let response = state
+        .web3_service
+        .get_balance(&address, &crypto_balance_request.chain)
+        .await?;
+    let rs = CryptoBalanceResponseDTO {
+        address,
+        balance: response.to_dto(),
+    };
+    Ok(get_success_response(&rs))
 }
 
-//allow user to get their wallet by their private key
-pub async fn crypto_wallet(event: Request) -> Result<Response<Body>, Error> {
-    let repository = Arc::new(InfuraRepository::new());
-    let web3_service = Web3Service::new(repository);
+//allow client get every balance of their wallet at once
+pub async fn crypto_portfolio<B, C>(
+    state: &AppState<B, C>,
+    event: Request,
+) -> Result<Response<Body>> {
+    let crypto_portfolio_request: CryptoPortfolioRequestDTO = parse_body(&event)?;
 
-    let body = event.body();
-    let body_str = String::from_utf8(body.as_ref().to_vec())?;
-    let crypto_wallet_request: CryptoWalletRequestDTO = serde_json::from_str(&body_str)?;
+    let address = state
+        .web3_service
+        .resolve_address(
+            crypto_portfolio_request.wallet_id.as_deref(),
+            crypto_portfolio_request.address.as_deref(),
+        )
+        .await?;
+    let response = state.web3_service.get_portfolio(&address).await?;
+    Ok(get_success_response(&response))
+}
 
-    match web3_service
-        .get_wallet(&crypto_wallet_request.signer_private_key)
-        .await
-    {
-        Ok(response) => {
-            let rs = CryptoWalletResponseDTO { address: response };
-            let json_str = to_string(&rs).unwrap();
-            Ok(get_success_response(json_str))
-        }
-        Err(err) => Ok(get_failed_response(err.to_string(), "Failed")),
-    }
+//allow user to get their wallet address by their wallet id
+pub async fn crypto_wallet<B, C>(state: &AppState<B, C>, event: Request) -> Result<Response<Body>> {
+    let crypto_wallet_request: CryptoWalletRequestDTO = parse_body(&event)?;
+
+    let response = state
+        .web3_service
+        .get_wallet(&crypto_wallet_request.wallet_id)
+        .await?;
+    let rs = CryptoWalletResponseDTO { address: response };
+    Ok(get_success_response(&rs))
 }
 
 //user can create their own wallet
-pub async fn crypto_wallet_creation(_: Request) -> Result<Response<Body>, Error> {
-    let repository = Arc::new(InfuraRepository::new());
-    let web3_service = Web3Service::new(repository);
-
-    match web3_service.create_wallet().await {
-        Ok(response) => {
-            let rs = CryptoWalletCreationResponseDTO {
-                address: response.0,
-                private_key: response.1,
-            };
-            let json_str = to_string(&rs).unwrap();
-            Ok(get_success_response(json_str))
-        }
-        Err(err) => Ok(get_failed_response(err.to_string(), "Failed")),
-    }
+pub async fn crypto_wallet_creation<B, C>(
+    state: &AppState<B, C>,
+    _: Request,
+) -> Result<Response<Body>> {
+    let response = state.web3_service.create_wallet().await?;
+    let rs = CryptoWalletCreationResponseDTO {
+        wallet_id: response.0,
+        address: response.1,
+    };
+    Ok(get_success_response(&rs))
 }
 
 //user can swap their crypto
-pub async fn crypto_swap(event: Request) -> Result<Response<Body>, Error> {
-    let repository = Arc::new(InfuraRepository::new());
-    let web3_service = Web3Service::new(repository);
-
-    let body = event.body();
-    let body_str = String::from_utf8(body.as_ref().to_vec())?;
-    let crypto_swap_request: CryptoSwapRequestDTO = serde_json::from_str(&body_str)?;
+pub async fn crypto_swap<B, C>(state: &AppState<B, C>, event: Request) -> Result<Response<Body>> {
+    let crypto_swap_request: CryptoSwapRequestDTO = parse_body(&event)?;
 
-    match web3_service
+    let response = state
+        .web3_service
         .swap(
             &crypto_swap_request.from_token,
             &crypto_swap_request.to_token,
             &crypto_swap_request.amount,
-            &crypto_swap_request.signer_private_key,
+            &crypto_swap_request.wallet_id,
+            &crypto_swap_request.options,
+        )
+        .await?;
+    let json_value = to_value(response.clone())?;
+    Ok(process_success_response(state, json_value, TransactionType::Swap, &response.address).await)
+}
+
+//integrator keeping its own key gets a transfer to sign
+pub async fn crypto_transaction_build<B, C>(
+    state: &AppState<B, C>,
+    event: Request,
+) -> Result<Response<Body>> {
+    let crypto_transaction_build_request: CryptoTransactionBuildRequestDTO = parse_body(&event)?;
+
+    let response = state
+        .web3_service
+        .build_transfer(
+            &crypto_transaction_build_request.sender_address,
+            &crypto_transaction_build_request.recipient_address,
+            &crypto_transaction_build_request.amount,
+            &crypto_transaction_build_request.chain,
+        )
+        .await?;
+    Ok(get_success_response(&response))
+}
+
+//integrator keeping its own key gets a swap to sign, with its approval when needed
+pub async fn crypto_swap_build<B, C>(
+    state: &AppState<B, C>,
+    event: Request,
+) -> Result<Response<Body>> {
+    let crypto_swap_build_request: CryptoSwapBuildRequestDTO = parse_body(&event)?;
+
+    let response = state
+        .web3_service
+        .build_swap(
+            &crypto_swap_build_request.from_token,
+            &crypto_swap_build_request.to_token,
+            &crypto_swap_build_request.amount,
+            &crypto_swap_build_request.address,
+            &crypto_swap_build_request.options,
         )
-        .await
-    {
-        Ok(response) => {
-            let json_value = serde_json::to_value(response.clone())?;
-            let final_response = process_success_response(
-                json_value,
-                TransactionType::Swap,
-                &response.clone().address,
-            )
-            .await;
-            Ok(final_response)
+        .await?;
+    Ok(get_success_response(&response))
+}
+
+//send a transaction the integrator signed, then record it like the custodial flows
+pub async fn crypto_broadcast<B, C>(
+    state: &AppState<B, C>,
+    event: Request,
+) -> Result<Response<Body>> {
+    let crypto_broadcast_request: CryptoBroadcastRequestDTO = parse_body(&event)?;
+
+    let response = state
+        .web3_service
+        .broadcast_transaction(
+            &crypto_broadcast_request.address,
+            &crypto_broadcast_request.signed_transaction,
+        )
+        .await?;
+    let tx_type = match response.operation.as_str() {
+        "swap" => TransactionType::Swap,
+        "approve" => TransactionType::Approve,
+        _ => TransactionType::CryptoTransfer,
+    };
+    let json_value = to_value(response.clone())?;
+    Ok(process_success_response(state, json_value, tx_type, &response.address).await)
+}
+
+//user can see what a swap would give before signing it
+pub async fn crypto_swap_quote<B, C>(
+    state: &AppState<B, C>,
+    event: Request,
+) -> Result<Response<Body>> {
+    let crypto_swap_quote_request: CryptoSwapQuoteRequestDTO = parse_body(&event)?;
+
+    let response = state
+        .web3_service
+        .swap_quote(
+            &crypto_swap_quote_request.from_token,
+            &crypto_swap_quote_request.to_token,
+            &crypto_swap_quote_request.amount,
+            crypto_swap_quote_request.wallet_id.as_deref(),
+            &crypto_swap_quote_request.options,
+        )
+        .await?;
+    Ok(get_success_response(&response))
+}
+
+//user can see what a transfer or a swap would cost before sending it
+pub async fn crypto_estimate<B, C>(
+    state: &AppState<B, C>,
+    event: Request,
+) -> Result<Response<Body>> {
+    let crypto_estimate_request: CryptoEstimateRequestDTO = parse_body(&event)?;
+
+    let response = match crypto_estimate_request {
+        CryptoEstimateRequestDTO::Transfer(transfer) => {
+            state
+                .web3_service
+                .estimate_transfer(
+                    &transfer.sender_wallet_id,
+                    &transfer.recipient_address,
+                    &transfer.amount,
+                    &transfer.chain,
+                    transfer.relayed,
+                )
+                .await?
         }
-        Err(err) => Ok(process_failed_response(
-            err.to_string(),
-            "Failed",
-            TransactionType::Swap,
-        )),
-    }
+        CryptoEstimateRequestDTO::Swap(swap) => {
+            state
+                .web3_service
+                .estimate_swap(
+                    &swap.from_token,
+                    &swap.to_token,
+                    &swap.amount,
+                    &swap.wallet_id,
+                    &swap.options,
+                )
+                .await?
+        }
+    };
+    Ok(get_success_response(&response))
 }
 
-//user can get their transaction history
-pub async fn transaction_history(event: Request) -> Result<Response<Body>, Error> {
-    let db_repository = Arc::new(PostgresRepository::new().await);
-    let db_service = DatabaseService::new(db_repository.clone());
+//user can wrap their native ETH into WETH
+pub async fn crypto_wrap<B, C>(state: &AppState<B, C>, event: Request) -> Result<Response<Body>> {
+    let crypto_wrap_request: CryptoWrapRequestDTO = parse_body(&event)?;
+
+    let response = state
+        .web3_service
+        .wrap(&crypto_wrap_request.wallet_id, &crypto_wrap_request.amount)
+        .await?;
+    let json_value = to_value(response.clone())?;
+    Ok(process_success_response(state, json_value, TransactionType::Wrap, &response.address).await)
+}
 
-    let body = event.body();
-    let body_str = String::from_utf8(body.as_ref().to_vec())?;
-    let transaction_history_request: TransactionHistoryRequestDTO =
-        serde_json::from_str(&body_str)?;
+//user can unwrap their WETH back into native ETH
+pub async fn crypto_unwrap<B, C>(state: &AppState<B, C>, event: Request) -> Result<Response<Body>> {
+    let crypto_unwrap_request: CryptoWrapRequestDTO = parse_body(&event)?;
 
-    let transaction_result = db_service
-        .fetch_related_transactions(&transaction_history_request.address.to_lowercase())
+    let response = state
+        .web3_service
+        .unwrap(
+            &crypto_unwrap_request.wallet_id,
+            &crypto_unwrap_request.amount,
+        )
+        .await?;
+    let json_value = to_value(response.clone())?;
+    Ok(process_success_response(
+        state,
+        json_value,
+        TransactionType::Unwrap,
+        &response.address,
+    )
+    .await)
+}
+
+//user can list the allowances their wallet gave on the registered tokens
+pub async fn crypto_allowances<B, C>(
+    state: &AppState<B, C>,
+    event: Request,
+) -> Result<Response<Body>> {
+    let crypto_allowance_request: CryptoAllowanceRequestDTO = parse_body(&event)?;
+
+    let response = state
+        .web3_service
+        .get_allowances(
+            &crypto_allowance_request.wallet_id,
+            crypto_allowance_request.spender.as_deref(),
+        )
+        .await?;
+    Ok(get_success_response(&response))
+}
+
+//user can set an allowance back to zero
+pub async fn crypto_allowance_revoke<B, C>(
+    state: &AppState<B, C>,
+    event: Request,
+) -> Result<Response<Body>> {
+    let crypto_revoke_request: CryptoAllowanceRevokeRequestDTO = parse_body(&event)?;
+
+    let response = state
+        .web3_service
+        .revoke_allowance(
+            &crypto_revoke_request.wallet_id,
+            &crypto_revoke_request.token,
+            crypto_revoke_request.spender.as_deref(),
+        )
         .await?;
+    let json_value = to_value(response.clone())?;
+    Ok(process_success_response(
+        state,
+        json_value,
+        TransactionType::Revoke,
+        &response.address,
+    )
+    .await)
+}
+
+//user can deposit two tokens into their liquidity pool
+pub async fn crypto_liquidity_add<B, C>(
+    state: &AppState<B, C>,
+    event: Request,
+) -> Result<Response<Body>> {
+    let crypto_liquidity_request: CryptoLiquidityAddRequestDTO = parse_body(&event)?;
+
+    let response = state
+        .web3_service
+        .add_liquidity(
+            &crypto_liquidity_request.wallet_id,
+            &crypto_liquidity_request.token_a,
+            &crypto_liquidity_request.token_b,
+            &crypto_liquidity_request.amount_a,
+            &crypto_liquidity_request.amount_b,
+            &crypto_liquidity_request.options,
+        )
+        .await?;
+    let json_value = to_value(response.clone())?;
+    Ok(process_success_response(
+        state,
+        json_value,
+        TransactionType::AddLiquidity,
+        &response.address,
+    )
+    .await)
+}
+
+//user can burn their LP tokens and withdraw both tokens
+pub async fn crypto_liquidity_remove<B, C>(
+    state: &AppState<B, C>,
+    event: Request,
+) -> Result<Response<Body>> {
+    let crypto_liquidity_request: CryptoLiquidityRemoveRequestDTO = parse_body(&event)?;
+
+    let response = state
+        .web3_service
+        .remove_liquidity(
+            &crypto_liquidity_request.wallet_id,
+            &crypto_liquidity_request.token_a,
+            &crypto_liquidity_request.token_b,
+            &crypto_liquidity_request.liquidity,
+            &crypto_liquidity_request.options,
+        )
+        .await?;
+    let json_value = to_value(response.clone())?;
+    Ok(process_success_response(
+        state,
+        json_value,
+        TransactionType::RemoveLiquidity,
+        &response.address,
+    )
+    .await)
+}
+
+//user can see their LP balance and share of a pool
+pub async fn crypto_liquidity<B, C>(
+    state: &AppState<B, C>,
+    event: Request,
+) -> Result<Response<Body>> {
+    let crypto_liquidity_request: CryptoLiquidityPositionRequestDTO = parse_body(&event)?;
+
+    let response = state
+        .web3_service
+        .get_liquidity_position(
+            &crypto_liquidity_request.wallet_id,
+            &crypto_liquidity_request.token_a,
+            &crypto_liquidity_request.token_b,
+        )
+        .await?;
+    Ok(get_success_response(&response))
+}
 
-    let chain_repository = Arc::new(CelestiaRepository::new().await);
-    let chain_service = ChainService::new(chain_repository.clone());
+//user can get their transaction history
+pub async fn transaction_history<B, C>(
+    state: &AppState<B, C>,
+    event: Request,
+) -> Result<Response<Body>> {
+    let transaction_history_request: TransactionHistoryRequestDTO = parse_body(&event)?;
+
+    let transaction_result = state
+        .database_service
+        .fetch_related_transactions(&transaction_history_request.address.to_lowercase())
+        .await?;
 
     let mut transaction_from_node = Vec::<TransactionHistoryResponseDTO>::new();
     for tx in transaction_result {
-        println!("height : {:?}, address : {:?}", tx.w3_height, tx.w3_address);
-        let namespace = chain_repository
-            .clone()
-            .address_to_namespace(&tx.w3_address)?;
-        let blobs = chain_service
-            .get_all(&[namespace], tx.w3_height.parse::<u64>().unwrap())
-            .await?;
-        transaction_from_node.append(&mut blobs.clone());
+        let namespace = state.chain_service.address_to_namespace(&tx.w3_address)?;
+        let height = tx.w3_height.parse::<u64>().map_err(|_| {
+            DomainError::Internal(format!("Invalid stored height {}", tx.w3_height))
+        })?;
+        let mut blobs = state.chain_service.get_all(&[namespace], height).await?;
+        transaction_from_node.append(&mut blobs);
     }
-    let json_str = to_string(&transaction_from_node).unwrap();
-    Ok(get_success_response(json_str))
+    Ok(get_success_response(&transaction_from_node))
+}
+
+//admin can list the registered tokens
+pub async fn admin_tokens<B, C>(state: &AppState<B, C>, event: Request) -> Result<Response<Body>> {
+    require_admin(&event)?;
+
+    let tokens = state.token_service.list_tokens().await?;
+    Ok(get_success_response(&tokens))
+}
+
+//admin can register a new token
+pub async fn admin_token_creation<B, C>(
+    state: &AppState<B, C>,
+    event: Request,
+) -> Result<Response<Body>> {
+    require_admin(&event)?;
+    let token_request: TokenDTO = parse_body(&event)?;
+
+    let token = state.token_service.add_token(token_request).await?;
+    Ok(get_success_response(&token))
 }
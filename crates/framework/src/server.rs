@@ -0,0 +1,78 @@
+use crate::{handler::lambda_handler, state::ServiceState};
+use lambda_http::{http, Body};
+use rocket::{
+    data::{Data, ToByteUnit},
+    http::{ContentType, Method, Status},
+    route::{Handler, Outcome},
+    Build, Request, Rocket, Route,
+};
+use std::sync::Arc;
+
+//every rocket request is converted and forwarded to the same dispatcher the lambda function uses
+#[derive(Clone)]
+struct LambdaBridge {
+    state: Arc<ServiceState>,
+}
+
+#[rocket::async_trait]
+impl Handler for LambdaBridge {
+    async fn handle<'r>(&self, req: &'r Request<'_>, data: Data<'r>) -> Outcome<'r> {
+        let body = match data.open(1.mebibytes()).into_bytes().await {
+            Ok(bytes) => bytes.into_inner(),
+            Err(_) => return Outcome::from(req, Status::BadRequest),
+        };
+
+        //rebuild the incoming request as a lambda request
+        let mut builder = http::Request::builder()
+            .method(req.method().as_str())
+            .uri(req.uri().to_string());
+        for header in req.headers().iter() {
+            builder = builder.header(header.name().as_str(), header.value());
+        }
+        let event = match builder.body(Body::from(body)) {
+            Ok(event) => event,
+            Err(_) => return Outcome::from(req, Status::BadRequest),
+        };
+
+        let response = match lambda_handler(&self.state, event).await {
+            Ok(response) => response,
+            Err(_) => return Outcome::from(req, Status::InternalServerError),
+        };
+
+        //convert the lambda response back into a rocket response
+        let status = Status::new(response.status().as_u16());
+        let content_type = response
+            .headers()
+            .get("Content-Type")
+            .and_then(|value| value.to_str().ok())
+            .and_then(ContentType::parse_flexible)
+            .unwrap_or(ContentType::JSON);
+        let body = match response.into_body() {
+            Body::Empty => Vec::new(),
+            Body::Text(text) => text.into_bytes(),
+            Body::Binary(bytes) => bytes,
+        };
+        Outcome::from(req, (status, (content_type, body)))
+    }
+}
+
+//mount a catch all route for every method so the paths stay defined in one place (handler.rs)
+pub fn build_rocket(state: Arc<ServiceState>) -> Rocket<Build> {
+    let routes: Vec<Route> = [
+        Method::Get,
+        Method::Post,
+        Method::Put,
+        Method::Delete,
+        Method::Patch,
+    ]
+    .into_iter()
+    .map(|method| {
+        let bridge = LambdaBridge {
+            state: state.clone(),
+        };
+        Route::new(method, "/<path..>", bridge)
+    })
+    .collect();
+
+    rocket::build().mount("/", routes)
+}
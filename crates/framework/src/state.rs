@@ -0,0 +1,77 @@
+use app::usecase::{
+    chain_service::ChainService, database_service::DatabaseService,
+    payment_service::PaymentService, token_service::TokenService, web3_service::Web3Service,
+};
+use celestia_types::{nmt::Namespace, Blob};
+use domain::repository::{
+    chain_repository::ChainRepository, database_repository::DatabaseRepository,
+    payment_repository::PaymentRepository, token_repository::TokenRepository,
+    web3_repository::Web3Repository,
+};
+use infra::{
+    celestia_repository::CelestiaRepository, circle_repository::CircleRepository, config,
+    infura_repository::InfuraRepository, postgres_repository::PostgresRepository,
+};
+use std::sync::Arc;
+
+//services shared by every request, built once per process
+pub struct AppState<B, C> {
+    pub payment_service: PaymentService,
+    pub web3_service: Web3Service,
+    pub chain_service: ChainService<B, C>,
+    pub database_service: DatabaseService,
+    pub token_service: TokenService,
+    //nothing is broadcast, so nothing is indexed either
+    pub dry_run: bool,
+}
+
+//the state used by the deployed service
+pub type ServiceState = AppState<Blob, Namespace>;
+
+impl<B, C> AppState<B, C> {
+    pub fn new(
+        payment_repository: Arc<dyn PaymentRepository>,
+        web3_repository: Arc<dyn Web3Repository>,
+        chain_repository: Arc<dyn ChainRepository<B, C> + Send + Sync>,
+        database_repository: Arc<dyn DatabaseRepository>,
+        token_repository: Arc<dyn TokenRepository>,
+        dry_run: bool,
+    ) -> Self {
+        Self {
+            payment_service: PaymentService::new(payment_repository),
+            web3_service: Web3Service::new(web3_repository),
+            chain_service: ChainService::new(chain_repository),
+            database_service: DatabaseService::new(database_repository),
+            token_service: TokenService::new(token_repository),
+            dry_run,
+        }
+    }
+}
+
+impl ServiceState {
+    //build the real repositories from the environment, this should run at cold start
+    pub async fn from_env() -> Self {
+        //postgres backs the history index, the wallet keystore, the token registry and the wallet nonces
+        let database_repository = Arc::new(PostgresRepository::new().await);
+        let payment_repository = Arc::new(CircleRepository::new());
+        let web3_repository = Arc::new(InfuraRepository::new(
+            database_repository.clone(),
+            database_repository.clone(),
+            database_repository.clone(),
+        ));
+        //refuse to start against a node on another chain than the configured one
+        web3_repository
+            .verify_chain_id()
+            .await
+            .expect("Node must serve the expected chain");
+        let chain_repository = Arc::new(CelestiaRepository::new().await);
+        Self::new(
+            payment_repository,
+            web3_repository,
+            chain_repository,
+            database_repository.clone(),
+            database_repository,
+            config::dry_run_enabled(),
+        )
+    }
+}
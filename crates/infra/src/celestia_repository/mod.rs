@@ -1,3 +1,4 @@
+use crate::config;
 use anyhow::{anyhow, Ok, Result};
 use async_trait::async_trait;
 use base64::prelude::BASE64_STANDARD;
@@ -16,6 +17,8 @@ pub struct CelestiaRepository {
     pub client: Client,
     pub url: String,
     pub auth_token: String,
+    //blobs are built but never submitted
+    dry_run: bool,
 }
 
 impl CelestiaRepository {
@@ -29,6 +32,7 @@ impl CelestiaRepository {
             client,
             url,
             auth_token,
+            dry_run: config::dry_run_enabled(),
         }
     }
 
@@ -36,26 +40,16 @@ impl CelestiaRepository {
         static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
         LOCK.get_or_init(|| Mutex::new(())).lock().await
     }
-
-    pub fn address_to_namespace(&self, address: &str) -> Result<Namespace> {
-        // Remove "0x" if present.
-        let addr = address.strip_prefix("0x").unwrap_or(address);
-        // Decode the hex string into raw bytes.
-        let decoded = BASE64_STANDARD.decode(addr)?;
-        if decoded.len() < 8 {
-            return Err(anyhow!("Address decoded bytes less than 8"));
-        }
-        // Take the first 8 bytes.
-        let mut ns = [0u8; 8];
-        ns.copy_from_slice(&decoded[..8]);
-        let namespace: Namespace = Namespace::new_v0(&ns)?;
-        Ok(namespace)
-    }
 }
 
 #[async_trait]
 impl ChainRepository<Blob, Namespace> for CelestiaRepository {
     async fn submit(&self, blobs: &[Blob]) -> Result<u64> {
+        //in dry run the current head stands in for the height the blobs would land at
+        if self.dry_run {
+            let head = self.client.header_network_head().await?;
+            return Ok(head.height().value());
+        }
         let _guard = self.write_lock().await;
         let height = self.client.blob_submit(blobs, TxConfig::default()).await?;
         Ok(height)
@@ -68,15 +62,12 @@ impl ChainRepository<Blob, Namespace> for CelestiaRepository {
     ) -> Result<Vec<TransactionHistoryResponseDTO>> {
         let blobs = self.client.blob_get_all(height, namespace).await?;
         let mut rs = Vec::<TransactionHistoryResponseDTO>::new();
-        match blobs {
-            Some(blobs) => {
-                for blob in blobs {
-                    println!("found at height {:?}", height);
-                    let data = self.revert_blob(&blob)?;
-                    rs.push(data);
-                }
+        if let Some(blobs) = blobs {
+            for blob in blobs {
+                tracing::debug!("found at height {:?}", height);
+                let data = self.revert_blob(&blob)?;
+                rs.push(data);
             }
-            None => {}
         }
         Ok(rs)
     }
@@ -92,7 +83,7 @@ impl ChainRepository<Blob, Namespace> for CelestiaRepository {
         let json_str = String::from_utf8(decoded_bytes)?;
 
         // Deserialize the JSON string back into a serde_json::Value.
-        let data = serde_json::from_str::<TransactionHistoryResponseDTO>(&json_str).unwrap();
+        let data = serde_json::from_str::<TransactionHistoryResponseDTO>(&json_str)?;
 
         Ok(data)
     }
@@ -104,4 +95,19 @@ impl ChainRepository<Blob, Namespace> for CelestiaRepository {
         let blob: Blob = Blob::new(namespace, encoded_data, AppVersion::V2)?;
         Ok(blob)
     }
+
+    fn address_to_namespace(&self, address: &str) -> Result<Namespace> {
+        // Remove "0x" if present.
+        let addr = address.strip_prefix("0x").unwrap_or(address);
+        // Decode the hex string into raw bytes.
+        let decoded = BASE64_STANDARD.decode(addr)?;
+        if decoded.len() < 8 {
+            return Err(anyhow!("Address decoded bytes less than 8"));
+        }
+        // Take the first 8 bytes.
+        let mut ns = [0u8; 8];
+        ns.copy_from_slice(&decoded[..8]);
+        let namespace: Namespace = Namespace::new_v0(&ns)?;
+        Ok(namespace)
+    }
 }
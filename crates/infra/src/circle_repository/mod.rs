@@ -1,22 +1,34 @@
+use crate::config;
 use anyhow::Result;
 use async_trait::async_trait;
 use chrono::Utc;
 use domain::{
-    repository::payment_repository::PaymentRepository, shared::dtos::FiatTransactionResponseDTO,
+    repository::payment_repository::PaymentRepository,
+    shared::{amount::TokenAmount, dtos::FiatTransactionResponseDTO, error::DomainError},
 };
-use ethers::types::transaction::response;
 use request_model::{Amount, Destination, Source, TransferRequest};
-use reqwest::{header::CONTENT_TYPE, Client, Error, RequestBuilder, Response};
+use reqwest::{header::CONTENT_TYPE, Client, RequestBuilder, Response};
 use serde::Serialize;
 use serde_json::{self, to_string, Value};
 use std::env;
 use uuid::Uuid;
 pub mod request_model;
 
+//circle transfers are expressed in USD cents precision
+const USD_DECIMALS: u8 = 2;
+
 pub struct CircleRepository {
     client: Client,
     mint_base_url: String,
     mint_api_key: String,
+    //transfers are built but never posted
+    dry_run: bool,
+}
+
+impl Default for CircleRepository {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 pub enum RequestMethod {
@@ -30,6 +42,7 @@ impl CircleRepository {
             client: Client::new(),
             mint_api_key: env::var("CIRCLE_MINT_API_KEY").expect("Circle mint API Key must be set"),
             mint_base_url: env::var("CIRCLE_MINT_BASE_URL").expect("Mint base url must be set"),
+            dry_run: config::dry_run_enabled(),
         }
     }
 
@@ -38,9 +51,9 @@ impl CircleRepository {
         body: Option<T>,
         endpoint: &String,
         method: RequestMethod,
-    ) -> Result<Response, Error> {
+    ) -> Result<Response> {
         let json = match body {
-            Some(b) => to_string(&b).unwrap(),
+            Some(b) => to_string(&b)?,
             None => "".to_string(),
         };
         let mut request: RequestBuilder = match method {
@@ -52,20 +65,31 @@ impl CircleRepository {
             .header(CONTENT_TYPE, "application/json")
             .body(json.clone());
 
-        let response = request
-            .send()
-            .await
-            .map_err(|e| format!("[ERROR] Circle Endpoint: {endpoint} - details: {e}"));
+        //the endpoint, the http client error and circle's body stay in the logs
+        let response = request.send().await.map_err(|e| {
+            tracing::error!("Circle Endpoint: {endpoint} - details: {e}");
+            DomainError::Upstream("Circle is unavailable".to_string())
+        })?;
 
-        Ok(response.unwrap())
+        //circle answers business errors with a non 2xx status and a json message
+        if !response.status().is_success() {
+            let status = response.status();
+            let details = response.text().await.unwrap_or_default();
+            tracing::error!("Circle Endpoint: {endpoint} - status: {status} - details: {details}");
+            return Err(DomainError::Upstream(format!(
+                "Circle rejected the request with status {}",
+                status.as_u16()
+            ))
+            .into());
+        }
+
+        Ok(response)
     }
 
-    async fn get_master_wallet_id(&self) -> Result<Response, Error> {
+    async fn get_master_wallet_id(&self) -> Result<Response> {
         let endpoint = format!("{}/v1/configuration", self.mint_base_url);
-        let response = self
-            .send_request(None::<()>, &endpoint, RequestMethod::GET)
-            .await;
-        Ok(response.unwrap())
+        self.send_request(None::<()>, &endpoint, RequestMethod::GET)
+            .await
     }
 }
 
@@ -78,23 +102,33 @@ impl PaymentRepository for CircleRepository {
         destination_address: &str,
     ) -> Result<FiatTransactionResponseDTO> {
         let endpoint = format!("{}/v1/transfers", self.mint_base_url);
+        let usd_amount = TokenAmount::parse(amount, USD_DECIMALS)?;
         let json_wallet = self
             .get_master_wallet_id()
-            .await
-            .unwrap()
+            .await?
             .text()
             .await
-            .unwrap();
-        let wallet_value: Value = serde_json::from_str(&json_wallet).unwrap();
-        let master_wallet_id = wallet_value["data"]["payments"]["masterWalletId"].as_str();
+            .map_err(|e| {
+                tracing::error!("failed to read the Circle configuration : {}", e);
+                DomainError::Upstream("Circle is unavailable".to_string())
+            })?;
+        let wallet_value: Value = serde_json::from_str(&json_wallet).map_err(|e| {
+            tracing::error!("invalid Circle configuration : {}", e);
+            DomainError::Upstream("Invalid Circle configuration".to_string())
+        })?;
+        let master_wallet_id = wallet_value["data"]["payments"]["masterWalletId"]
+            .as_str()
+            .ok_or_else(|| {
+                DomainError::Upstream("Circle master wallet id not found".to_string())
+            })?;
 
         let payload = TransferRequest {
             source: Source {
-                id: master_wallet_id.unwrap_or_default().to_string(),
+                id: master_wallet_id.to_string(),
                 source_type: "wallet".to_string(),
             },
             amount: Amount {
-                amount: amount.to_string(),
+                amount: usd_amount.format(),
                 currency: "USD".to_string(),
             },
             destination: Destination {
@@ -105,12 +139,13 @@ impl PaymentRepository for CircleRepository {
             idempotency_key: Uuid::new_v4().to_string(),
         };
 
-        let response = self
-            .send_request(Some(payload), &endpoint, RequestMethod::POST)
-            .await;
+        if !self.dry_run {
+            self.send_request(Some(payload), &endpoint, RequestMethod::POST)
+                .await?;
+        }
         let result = FiatTransactionResponseDTO {
             receipient_address: destination_address.to_string(),
-            amount: amount.to_string(),
+            amount: usd_amount.to_dto(),
             timestamp: Utc::now().timestamp().to_string(),
         };
         Ok(result)
@@ -0,0 +1,20 @@
+use std::env;
+
+//DRY_RUN=true builds and signs every transaction, transfer and blob but never broadcasts them
+pub fn dry_run_enabled() -> bool {
+    matches!(
+        env::var("DRY_RUN")
+            .unwrap_or_default()
+            .to_lowercase()
+            .as_str(),
+        "true" | "1"
+    )
+}
+
+//the only chain signers may be set up on, a wrong INFURA_BASE_URL must not reach another network
+pub fn expected_chain_id() -> u64 {
+    env::var("EXPECTED_CHAIN_ID")
+        .expect("EXPECTED_CHAIN_ID must be set")
+        .parse::<u64>()
+        .expect("EXPECTED_CHAIN_ID must be a chain id number")
+}
@@ -1,4 +1,20 @@
+pub const CT_ERC20: &str = include_str!("ct-erc20.json");
 pub const CT_USDC: &str = include_str!("ct-usdc.json");
-pub const CT_LINK: &str = include_str!("ct-link.json");
 pub const CT_WETH: &str = include_str!("ct-weth.json");
 pub const CT_ROUTER02: &str = include_str!("ct-router02.json");
+pub const CT_FACTORY02: &str = include_str!("ct-factory02.json");
+pub const CT_PAIR: &str = include_str!("ct-pair.json");
+pub const CT_ERC20_PERMIT: &str = include_str!("ct-erc20-permit.json");
+pub const CT_MULTICALL3: &str = include_str!("ct-multicall3.json");
+
+//every ABI above, searched for the custom error matching a revert selector
+pub const BUNDLED_ABIS: [&str; 8] = [
+    CT_ERC20,
+    CT_USDC,
+    CT_WETH,
+    CT_ROUTER02,
+    CT_FACTORY02,
+    CT_PAIR,
+    CT_ERC20_PERMIT,
+    CT_MULTICALL3,
+];
@@ -1,78 +1,141 @@
-use crate::contract_abi::{CT_LINK, CT_ROUTER02, CT_USDC, CT_WETH};
-use anyhow::{anyhow, Ok, Result};
+use crate::{
+    config,
+    contract_abi::{
+        BUNDLED_ABIS, CT_ERC20, CT_ERC20_PERMIT, CT_FACTORY02, CT_MULTICALL3, CT_PAIR, CT_ROUTER02,
+        CT_USDC, CT_WETH,
+    },
+    keystore::KeystoreCipher,
+    nonce_manager::SharedNonceMiddleware,
+};
+use anyhow::{Ok, Result};
 use async_trait::async_trait;
 use chrono::Utc;
 use domain::{
-    repository::web3_repository::Web3Repository,
-    shared::dtos::{CryptoSwapResponseDTO, CryptoTransactionResponseDTO},
+    entity::{W3Token, W3Wallet},
+    repository::{
+        keystore_repository::KeystoreRepository, nonce_repository::NonceRepository,
+        token_repository::TokenRepository, web3_repository::Web3Repository,
+    },
+    shared::{
+        amount::TokenAmount,
+        dtos::{
+            AmountDTO, CryptoAllowanceResponseDTO, CryptoAllowanceRevokeResponseDTO,
+            CryptoBroadcastResponseDTO, CryptoEstimateResponseDTO, CryptoHoldingDTO,
+            CryptoLiquidityPositionResponseDTO, CryptoLiquidityResponseDTO,
+            CryptoPortfolioResponseDTO, CryptoReplacementResponseDTO, CryptoSwapBuildResponseDTO,
+            CryptoSwapQuoteResponseDTO, CryptoSwapResponseDTO, CryptoTransactionBuildResponseDTO,
+            CryptoTransactionResponseDTO, CryptoTransactionStatusResponseDTO,
+            CryptoUnsignedTransactionDTO, CryptoWrapResponseDTO, LiquidityOptionsDTO,
+            SwapOptionsDTO, TransactionStatus,
+        },
+        error::DomainError,
+    },
 };
 use ethers::{
     abi::Abi,
-    core::rand::thread_rng,
+    core::rand::{thread_rng, RngCore},
     prelude::*,
-    utils::{self, parse_units},
+    types::transaction::eip2718::TypedTransaction,
 };
 use std::{
     env,
-    sync::Arc,
-    time::{SystemTime, UNIX_EPOCH},
+    fmt::{self, Display},
+    sync::{Arc, OnceLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
+use uuid::Uuid;
+
+//slippage tolerance applied to the expected output, in basis points
+const DEFAULT_SLIPPAGE_BPS: u32 = 500;
+const DEFAULT_MAX_SLIPPAGE_BPS: u32 = 1_000;
+//uniswap deadlines are unix timestamps in seconds
+const DEFAULT_DEADLINE_SECONDS: u64 = 300;
+const DEFAULT_MAX_DEADLINE_SECONDS: u64 = 3_600;
+//uniswap v2 pairs keep 0.3% of every input
+const PAIR_FEE_NUMERATOR: u64 = 997;
+const PAIR_FEE_DENOMINATOR: u64 = 1000;
+//gas used when the node cannot simulate the call, e.g. the router is not approved yet
+const SWAP_GAS_FALLBACK: u64 = 150_000;
+const SWAP_HOP_GAS_FALLBACK: u64 = 60_000;
+const APPROVE_GAS_FALLBACK: u64 = 50_000;
+//a swap sent right behind its approval cannot be simulated, the allowance only exists once the approval is mined
+const UNAPPROVED_SWAP_GAS_LIMIT: u64 = 350_000;
+//same for a liquidity deposit, which also deploys the pair when the pool does not exist yet
+const UNAPPROVED_LIQUIDITY_GAS_LIMIT: u64 = 400_000;
+const UNAPPROVED_NEW_PAIR_GAS_LIMIT: u64 = 4_000_000;
+//an approval and the call behind it are both awaited, each one within this bound to stay under the 30 second lambda timeout
+const RECEIPT_TIMEOUT_SECONDS: u64 = 12;
+const RECEIPT_POLL_SECONDS: u64 = 2;
+//the only message a client gets for node and transport failures
+const UPSTREAM_UNAVAILABLE: &str = "Upstream provider unavailable";
+//uniswap v2 LP tokens always have 18 decimals
+const LP_DECIMALS: u8 = 18;
+const NATIVE_DECIMALS: u8 = 18;
+//validity of an EIP-3009 authorization submitted by the relayer
+const RELAYED_AUTHORIZATION_SECONDS: u64 = 3_600;
+//gas quoted for a transferWithAuthorization, it cannot be estimated before the sender signs it
+const RELAYED_TRANSFER_GAS_FALLBACK: u64 = 100_000;
+//smallest relayed transfer in units of the token, dust would cost the relayer more gas than it moves
+const DEFAULT_RELAYED_MIN_AMOUNT: &str = "1";
+//registered tokens tried as the middle hop when a pair has no direct pool
+const ROUTE_INTERMEDIATES: [&str; 2] = ["WETH", "USDC"];
+//Multicall3 is deployed at the same address on every public network
+const DEFAULT_MULTICALL3_ADDRESS: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+//stable coin the portfolio is valued in
+const VALUATION_TOKEN: &str = "USDC";
+//nodes only replace a pending transaction when both fees rise by at least 10%
+const REPLACEMENT_FEE_BUMP_BPS: u64 = 1_250;
+//a plain ETH transfer, what a cancel sends
+const CANCEL_GAS_LIMIT: u64 = 21_000;
+
+//custodial wallet signer, its nonces are shared with every other instance of the service
+type WalletClient = SharedNonceMiddleware<SignerMiddleware<Provider<Http>, LocalWallet>>;
 
 pub struct InfuraRepository {
     pub provider: Provider<Http>,
     pub base_url: String,
     pub api_key: String,
+    pub network: String,
+    router_address: Address,
+    multicall_address: Address,
+    keystore: Arc<dyn KeystoreRepository>,
+    tokens: Arc<dyn TokenRepository>,
+    nonces: Arc<dyn NonceRepository>,
+    cipher: KeystoreCipher,
+    max_slippage_bps: u32,
+    max_deadline_seconds: u64,
+    //service-owned custodial wallet paying the gas of relayed transfers
+    relayer_wallet_id: Option<String>,
+    relayed_min_amount: String,
+    //signers are only set up when the node serves this chain
+    expected_chain_id: u64,
+    //sign everything but broadcast nothing
+    dry_run: bool,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum ContractABI {
-    USDC,
-    LINK,
-    ETH,
-    WETH,
-    ROUTER02,
-    NONE,
+//which side of the swap the user amount fixes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapMode {
+    ExactInput,
+    ExactOutput,
 }
 
-impl ContractABI {
-    pub fn get_contract_abi(&self) -> String {
-        match self {
-            ContractABI::USDC => CT_USDC.to_string(),
-            ContractABI::LINK => CT_LINK.to_string(),
-            ContractABI::ETH => "".to_string(),
-            ContractABI::WETH => CT_WETH.to_string(),
-            ContractABI::ROUTER02 => CT_ROUTER02.to_string(),
-            ContractABI::NONE => "".to_string(),
-        }
-    }
-
-    pub fn get_contract_address(&self) -> String {
-        match self {
-            ContractABI::USDC => {
-                env::var("CONTRACT_USDC").expect("USDC contract address must be set")
-            }
-            ContractABI::LINK => {
-                env::var("CONTRACT_LINK").expect("LINK contract address must be set")
-            }
-            ContractABI::WETH => {
-                env::var("CONTRACT_WETH").expect("WETH contract address must be set")
-            }
-            ContractABI::ROUTER02 => {
-                env::var("CONTRACT_ROUTER02").expect("ROUTER02 contract address must be set")
-            }
-            _ => "".to_string(),
+impl SwapMode {
+    fn from_mode(mode: &str) -> Option<SwapMode> {
+        match mode.to_lowercase().as_str() {
+            "exact_input" => Some(SwapMode::ExactInput),
+            "exact_output" => Some(SwapMode::ExactOutput),
+            _ => None,
         }
     }
+}
 
-    pub fn map_token_contract(chain: &str) -> ContractABI {
-        match chain.to_lowercase().as_str() {
-            "usdc" => ContractABI::USDC,
-            "link" => ContractABI::LINK,
-            "eth" => ContractABI::ETH,
-            "weth" => ContractABI::WETH,
-            "router02" => ContractABI::ROUTER02,
-            _ => ContractABI::NONE,
-        }
+impl Display for SwapMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SwapMode::ExactInput => "exact_input",
+            SwapMode::ExactOutput => "exact_output",
+        })
     }
 }
 
@@ -82,337 +145,3820 @@ pub enum SwapMethod {
     SwapExactTokensForETH,
     SwapExactETHForTokens,
     SwapExactTokensForTokens,
+    SwapTokensForExactETH,
+    SwapETHForExactTokens,
+    SwapTokensForExactTokens,
+    SwapExactTokensForETHSupportingFeeOnTransferTokens,
+    SwapExactETHForTokensSupportingFeeOnTransferTokens,
+    SwapExactTokensForTokensSupportingFeeOnTransferTokens,
 }
 
 impl SwapMethod {
-    fn map_swap_method(from: ContractABI, to: ContractABI) -> Result<SwapMethod> {
-        match (from, to) {
-            (ContractABI::WETH, t) if t != ContractABI::WETH => {
-                Ok(SwapMethod::SwapExactETHForTokens)
+    //the flags tell whether each side of the pair is the native coin
+    //taxed tokens on the path need the supporting variants, which only exist for an exact input
+    fn map_swap_method(
+        from_native: bool,
+        to_native: bool,
+        mode: SwapMode,
+        fee_on_transfer: bool,
+    ) -> Result<SwapMethod> {
+        if fee_on_transfer {
+            return match (from_native, to_native, mode) {
+                (_, _, SwapMode::ExactOutput) => Err(DomainError::Validation(
+                    "Exact output is not supported for fee-on-transfer tokens".to_string(),
+                )
+                .into()),
+                (true, false, _) => {
+                    Ok(SwapMethod::SwapExactETHForTokensSupportingFeeOnTransferTokens)
+                }
+                (false, true, _) => {
+                    Ok(SwapMethod::SwapExactTokensForETHSupportingFeeOnTransferTokens)
+                }
+                (false, false, _) => {
+                    Ok(SwapMethod::SwapExactTokensForTokensSupportingFeeOnTransferTokens)
+                }
+                _ => Err(DomainError::Validation("Not support pairs to swap".to_string()).into()),
+            };
+        }
+        match (from_native, to_native, mode) {
+            (true, false, SwapMode::ExactInput) => Ok(SwapMethod::SwapExactETHForTokens),
+            (false, true, SwapMode::ExactInput) => Ok(SwapMethod::SwapExactTokensForETH),
+            (false, false, SwapMode::ExactInput) => Ok(SwapMethod::SwapExactTokensForTokens),
+            (true, false, SwapMode::ExactOutput) => Ok(SwapMethod::SwapETHForExactTokens),
+            (false, true, SwapMode::ExactOutput) => Ok(SwapMethod::SwapTokensForExactETH),
+            (false, false, SwapMode::ExactOutput) => Ok(SwapMethod::SwapTokensForExactTokens),
+            //all the others not match -> say we don't support
+            _ => Err(DomainError::Validation("Not support pairs to swap".to_string()).into()),
+        }
+    }
+
+    //the router receives msg.value, nothing has to be approved
+    fn pays_in_eth(&self) -> bool {
+        matches!(
+            self,
+            SwapMethod::SwapExactETHForTokens
+                | SwapMethod::SwapETHForExactTokens
+                | SwapMethod::SwapExactETHForTokensSupportingFeeOnTransferTokens
+        )
+    }
+
+    fn pays_out_eth(&self) -> bool {
+        matches!(
+            self,
+            SwapMethod::SwapExactTokensForETH
+                | SwapMethod::SwapTokensForExactETH
+                | SwapMethod::SwapExactTokensForETHSupportingFeeOnTransferTokens
+        )
+    }
+}
+
+//router function name
+impl Display for SwapMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SwapMethod::SwapExactETHForTokens => "swapExactETHForTokens",
+            SwapMethod::SwapExactTokensForETH => "swapExactTokensForETH",
+            SwapMethod::SwapExactTokensForTokens => "swapExactTokensForTokens",
+            SwapMethod::SwapTokensForExactETH => "swapTokensForExactETH",
+            SwapMethod::SwapETHForExactTokens => "swapETHForExactTokens",
+            SwapMethod::SwapTokensForExactTokens => "swapTokensForExactTokens",
+            SwapMethod::SwapExactTokensForETHSupportingFeeOnTransferTokens => {
+                "swapExactTokensForETHSupportingFeeOnTransferTokens"
             }
-            (f, ContractABI::WETH) if f != ContractABI::WETH => {
-                Ok(SwapMethod::SwapExactTokensForETH)
+            SwapMethod::SwapExactETHForTokensSupportingFeeOnTransferTokens => {
+                "swapExactETHForTokensSupportingFeeOnTransferTokens"
             }
-            (f, t) if f != ContractABI::WETH && t != ContractABI::WETH => {
-                Ok(SwapMethod::SwapExactTokensForTokens)
+            SwapMethod::SwapExactTokensForTokensSupportingFeeOnTransferTokens => {
+                "swapExactTokensForTokensSupportingFeeOnTransferTokens"
             }
-            //all the others not match -> say we don't support
-            _ => Err(anyhow!("Not support pairs to swap")),
-        }
+        })
     }
+}
+
+//how a spender got allowed to move the tokens of the signer
+enum Approval {
+    //the existing allowance already covers the amount
+    Covered,
+    //an approve transaction was sent and mined
+    Approved(String),
+    //an approve transaction was signed in dry run, the allowance never reaches the chain
+    Simulated(String),
+}
 
-    fn to_string(&self) -> String {
+impl Approval {
+    fn transaction_hash(&self) -> Option<String> {
         match self {
-            SwapMethod::SwapExactETHForTokens => "swapExactETHForTokens".to_string(),
-            SwapMethod::SwapExactTokensForETH => "swapExactTokensForETH".to_string(),
-            SwapMethod::SwapExactTokensForTokens => "swapExactTokensForTokens".to_string(),
+            Approval::Covered => None,
+            Approval::Approved(transaction_hash) => Some(transaction_hash.clone()),
+            Approval::Simulated(transaction_hash) => Some(transaction_hash.clone()),
         }
     }
+
+    //the following transaction cannot be simulated while the allowance is not on chain yet
+    fn is_pending(&self) -> bool {
+        matches!(self, Approval::Simulated(_))
+    }
+}
+
+//calls a client may broadcast, the ones the build endpoints produce
+enum SignedOperation {
+    //native ETH or a registered erc20 token
+    Transfer,
+    //a registered erc20 token approving the router
+    Approve,
+    //one of the swap functions of the router
+    Swap,
+}
+
+impl Display for SignedOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SignedOperation::Transfer => "transfer",
+            SignedOperation::Approve => "approve",
+            SignedOperation::Swap => "swap",
+        })
+    }
+}
+
+//EIP-712 signature of a token message (EIP-2612 permit, EIP-3009 authorization)
+struct TypedSignature {
+    v: u8,
+    r: [u8; 32],
+    s: [u8; 32],
+}
+
+impl TypedSignature {
+    //sign keccak256(0x1901 || domainSeparator || structHash) with the wallet key
+    fn sign(
+        wallet: &LocalWallet,
+        domain_separator: [u8; 32],
+        struct_hash: [u8; 32],
+    ) -> Result<Self> {
+        let digest = ethers::utils::keccak256(
+            [&[0x19u8, 0x01][..], &domain_separator[..], &struct_hash[..]].concat(),
+        );
+        let signature = wallet
+            .sign_hash(H256::from(digest))
+            .map_err(|e| DomainError::Internal(format!("Failed to sign message: {}", e)))?;
+        let mut r = [0u8; 32];
+        let mut s = [0u8; 32];
+        signature.r.to_big_endian(&mut r);
+        signature.s.to_big_endian(&mut s);
+        Ok(TypedSignature {
+            v: signature.v as u8,
+            r,
+            s,
+        })
+    }
+}
+
+//everything the router needs to know about a swap, shared by the quote and the real swap
+struct SwapPlan {
+    from_token: W3Token,
+    to_token: W3Token,
+    route: Vec<W3Token>,
+    path: Vec<Address>,
+    method: SwapMethod,
+    mode: SwapMode,
+    //a taxed token is somewhere on the path
+    fee_on_transfer: bool,
+    //exact input, or the input quoted by getAmountsIn for an exact output
+    amount_in: TokenAmount,
+    amount_in_max: U256,
+    //output quoted by the router, before any transfer fee
+    quoted_out: U256,
+    //quoted output net of the registered transfer fees, or the exact output asked for
+    expected_out: U256,
+    amount_out_min: U256,
+    slippage_bps: u32,
+}
+
+//uniswap v2 pool of two tokens, the reserves follow the order the tokens were asked in
+struct PairState {
+    address: Address,
+    reserve_a: U256,
+    reserve_b: U256,
+    total_supply: U256,
+}
+
+//gas and EIP-1559 fees of a transaction, in wei
+struct CostEstimate {
+    gas: U256,
+    base_fee: U256,
+    max_priority_fee: U256,
+    max_fee: U256,
+    //ETH attached to the transaction
+    value: U256,
+}
+
+impl CostEstimate {
+    //what the transaction should cost at the current base fee
+    fn expected_cost(&self) -> U256 {
+        self.gas * (self.base_fee + self.max_priority_fee) + self.value
+    }
+
+    //what the node requires the wallet to hold before accepting the transaction
+    fn max_cost(&self) -> U256 {
+        self.gas * self.max_fee + self.value
+    }
+}
+
+//swap options once checked against the server maximums
+struct SwapSettings {
+    mode: SwapMode,
+    slippage_bps: u32,
+    deadline_seconds: u64,
+    approve_max: bool,
+    recipient: Option<Address>,
+}
+
+impl SwapPlan {
+    fn path_symbols(&self) -> Vec<String> {
+        self.route
+            .iter()
+            .map(|token| token.symbol.clone())
+            .collect()
+    }
+
+    fn taxed_tokens(&self) -> Vec<&W3Token> {
+        self.route
+            .iter()
+            .filter(|token| token.fee_on_transfer)
+            .collect()
+    }
 }
 
 //core internal infura provider
 impl InfuraRepository {
-    pub fn new() -> Self {
+    pub fn new(
+        keystore: Arc<dyn KeystoreRepository>,
+        tokens: Arc<dyn TokenRepository>,
+        nonces: Arc<dyn NonceRepository>,
+    ) -> Self {
         let base_url = env::var("INFURA_BASE_URL").expect("Infura base url must be set");
         let api_key = env::var("INFURA_API_KEY").expect("Infura api key must be set");
+        let network = env::var("NETWORK").expect("Network name must be set");
+        let router_address = env::var("CONTRACT_ROUTER02")
+            .expect("ROUTER02 contract address must be set")
+            .parse::<Address>()
+            .expect("ROUTER02 contract address must be valid");
+        let multicall_address = env::var("MULTICALL3_ADDRESS")
+            .ok()
+            .filter(|value| !value.is_empty())
+            .unwrap_or_else(|| DEFAULT_MULTICALL3_ADDRESS.to_string())
+            .parse::<Address>()
+            .expect("MULTICALL3_ADDRESS must be valid");
+        let max_slippage_bps = env::var("MAX_SLIPPAGE_BPS")
+            .ok()
+            .filter(|value| !value.is_empty())
+            .map(|value| {
+                value
+                    .parse::<u32>()
+                    .expect("MAX_SLIPPAGE_BPS must be a number of basis points")
+            })
+            .unwrap_or(DEFAULT_MAX_SLIPPAGE_BPS);
+        assert!(
+            max_slippage_bps <= 10_000,
+            "MAX_SLIPPAGE_BPS must not exceed 10000"
+        );
+        let max_deadline_seconds = env::var("MAX_DEADLINE_SECONDS")
+            .ok()
+            .filter(|value| !value.is_empty())
+            .map(|value| {
+                value
+                    .parse::<u64>()
+                    .expect("MAX_DEADLINE_SECONDS must be a number of seconds")
+            })
+            .unwrap_or(DEFAULT_MAX_DEADLINE_SECONDS);
+        let relayer_wallet_id = env::var("RELAYER_WALLET_ID")
+            .ok()
+            .filter(|wallet_id| !wallet_id.is_empty());
+        let relayed_min_amount = env::var("RELAYED_TRANSFER_MIN_AMOUNT")
+            .ok()
+            .filter(|value| !value.is_empty())
+            .unwrap_or_else(|| DEFAULT_RELAYED_MIN_AMOUNT.to_string());
+        TokenAmount::parse(&relayed_min_amount, NATIVE_DECIMALS)
+            .expect("RELAYED_TRANSFER_MIN_AMOUNT must be a positive amount");
         let rpc_url = format!("{}/v3/{}", base_url, api_key);
-        let provider = Provider::<Http>::try_from(rpc_url).unwrap();
+        let provider = Provider::<Http>::try_from(rpc_url).expect("Infura rpc url must be valid");
         Self {
             provider,
             base_url,
             api_key,
+            network: network.to_lowercase(),
+            router_address,
+            multicall_address,
+            keystore,
+            tokens,
+            nonces,
+            cipher: KeystoreCipher::new(),
+            max_slippage_bps,
+            max_deadline_seconds,
+            relayer_wallet_id,
+            relayed_min_amount,
+            expected_chain_id: config::expected_chain_id(),
+            dry_run: config::dry_run_enabled(),
         }
     }
 
-    async fn parse_amount(
-        &self,
-        contract: Contract<SignerMiddleware<Provider<Http>, LocalWallet>>,
-        amount: &str,
-    ) -> Result<U256> {
-        let whole_amount = amount
-            .parse::<f64>()
-            .map_err(|e| anyhow!("Failed to parse amount: {}", e))?;
+    //a wallet must never sign for another chain than the configured one
+    pub async fn verify_chain_id(&self) -> Result<u64> {
+        let chain_id = self
+            .provider
+            .get_chainid()
+            .await
+            .map_err(upstream_error)?
+            .as_u64();
+        if chain_id != self.expected_chain_id {
+            return Err(DomainError::Internal(format!(
+                "Node serves chain {} but EXPECTED_CHAIN_ID is {}",
+                chain_id, self.expected_chain_id
+            ))
+            .into());
+        }
+        Ok(chain_id)
+    }
+
+    //look the symbol up in the token registry of the configured network
+    async fn resolve_token(&self, symbol: &str) -> Result<W3Token> {
+        let token = self
+            .tokens
+            .get_token(symbol, &self.network)
+            .await?
+            .ok_or_else(|| {
+                DomainError::Validation(format!(
+                    "Unsupported token {} on {}",
+                    symbol.to_uppercase(),
+                    self.network
+                ))
+            })?;
+        Ok(token)
+    }
+
+    //the router only knows erc20 pools, so native coins are routed through the WETH pools
+    //the flag tells whether the user side of the swap is the native coin itself
+    async fn resolve_swap_token(&self, symbol: &str) -> Result<(W3Token, bool)> {
+        let token = self.resolve_token(symbol).await?;
+        if token.is_native() {
+            return Ok((self.resolve_token("WETH").await?, true));
+        }
+        Ok((token, false))
+    }
+
+    //exact base units of a user amount, checked against the registered decimals
+    fn parse_amount(&self, token: &W3Token, amount: &str) -> Result<TokenAmount> {
+        Ok(token.parse_amount(amount)?)
+    }
 
-        let decimals: u8 = contract.method::<(), u8>("decimals", ())?.call().await?;
+    //amount read from the chain, expressed with the decimals of the token
+    fn to_token_amount(&self, token: &W3Token, base_units: U256) -> Result<TokenAmount> {
+        let base_units = u128::try_from(base_units).map_err(|_| {
+            DomainError::Internal(format!("{} amount exceeds supported range", token.symbol))
+        })?;
+        Ok(token.amount_from_base_units(base_units))
+    }
 
-        let multiplier = 10f64.powi(decimals as i32);
-        let decimal_amount = U256::from((whole_amount * multiplier) as u128);
-        Ok(decimal_amount)
+    //balance of any address, native coins are read from the account itself
+    async fn token_balance<M: Middleware>(
+        &self,
+        client: Arc<M>,
+        token: &W3Token,
+        owner: Address,
+    ) -> Result<U256> {
+        let balance: U256 = match token.is_native() {
+            true => self
+                .provider
+                .get_balance(owner, None)
+                .await
+                .map_err(upstream_error)?,
+            false => {
+                let token_contract = self.establish_contract_erc20(client, token)?;
+                token_contract
+                    .method("balanceOf", owner)?
+                    .call()
+                    .await
+                    .map_err(upstream_error)?
+            }
+        };
+        Ok(balance)
     }
 
-    async fn establish_contract_erc20(
+    //every registered token shares the standard erc20 abi
+    fn establish_contract_erc20<M: Middleware>(
         &self,
-        client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
-        contract: ContractABI,
-    ) -> Result<Contract<SignerMiddleware<Provider<Http>, LocalWallet>>> {
+        client: Arc<M>,
+        token: &W3Token,
+    ) -> Result<Contract<M>> {
         //get abi contract
-        let abi_string = contract.get_contract_abi();
-        let abi: Abi = serde_json::from_str(&abi_string).unwrap();
+        let abi: Abi = serde_json::from_str(CT_ERC20)?;
 
-        //get contract address base on chain -> this address will be taken on the .env file
-        let contract_address = contract.get_contract_address().parse::<Address>().unwrap();
+        //get contract address from the token registry
+        let contract_address = self.token_address(token)?;
 
         //build the contract token
         let contract = Contract::new(contract_address, abi, client);
         Ok(contract)
     }
 
-    async fn establish_contract_router(
+    fn establish_contract_router<M: Middleware>(&self, client: Arc<M>) -> Result<Contract<M>> {
+        //get abi contract
+        let abi: Abi = serde_json::from_str(CT_ROUTER02)?;
+
+        //build the router contract, its address is taken on the .env file
+        let contract = Contract::new(self.router_address, abi, client);
+        Ok(contract)
+    }
+
+    //the only place where a stored key is decrypted
+    async fn establish_signer_wallet(&self, wallet_id: &str) -> Result<Arc<WalletClient>> {
+        //check the infura provider is on the expected chain
+        let chain_id = self.verify_chain_id().await?;
+
+        //build the wallet base on the decrypted key of the custodial wallet
+        let stored_wallet = self.keystore.get_wallet(wallet_id).await?;
+        let private_key = self
+            .cipher
+            .decrypt(&stored_wallet.w3_address, &stored_wallet.w3_keystore)?;
+        let wallet: LocalWallet = LocalWallet::from_bytes(&private_key)
+            .map_err(|_| DomainError::Internal("Corrupted wallet keystore".to_string()))?
+            .with_chain_id(chain_id);
+        if format!("{:?}", wallet.address()) != stored_wallet.w3_address.to_lowercase() {
+            return Err(DomainError::Internal("Corrupted wallet keystore".to_string()).into());
+        }
+
+        //combine both of those above to a client provider, nonces are reserved in the database
+        let address = wallet.address();
+        let client = Arc::new(SharedNonceMiddleware::new(
+            SignerMiddleware::new(self.provider.clone(), wallet.with_chain_id(chain_id)),
+            address,
+            self.nonces.clone(),
+            self.dry_run,
+        ));
+
+        Ok(client)
+    }
+
+    //WETH keeps its own abi for deposit and withdraw
+    fn establish_contract_weth<M: Middleware>(
         &self,
-        client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
-        contract: ContractABI,
-    ) -> Result<Contract<SignerMiddleware<Provider<Http>, LocalWallet>>> {
+        client: Arc<M>,
+        token: &W3Token,
+    ) -> Result<Contract<M>> {
         //get abi contract
-        let abi_string = contract.get_contract_abi();
-        let abi: Abi = serde_json::from_str(&abi_string).unwrap();
+        let abi: Abi = serde_json::from_str(CT_WETH)?;
 
-        //get contract address base on chain -> this address will be taken on the .env file
-        let contract_address = contract.get_contract_address().parse::<Address>().unwrap();
+        //get contract address from the token registry
+        let contract_address = self.token_address(token)?;
 
-        //build the contract token
         let contract = Contract::new(contract_address, abi, client);
         Ok(contract)
     }
 
-    async fn establish_signer_wallet(
+    //current allowance given by the owner to a spender
+    async fn get_allowance<M: Middleware>(
         &self,
-        signer_private_key: &str,
-    ) -> Result<Arc<SignerMiddleware<Provider<Http>, LocalWallet>>> {
-        //build the infura provider
-        let chain_id = self.provider.clone().get_chainid().await.unwrap();
+        client: Arc<M>,
+        token: &W3Token,
+        owner: Address,
+        spender: Address,
+    ) -> Result<U256> {
+        let token_contract = self.establish_contract_erc20(client, token)?;
+        let allowance: U256 = token_contract
+            .method("allowance", (owner, spender))?
+            .call()
+            .await
+            .map_err(upstream_error)?;
+        Ok(allowance)
+    }
 
-        //build the wallet base on signer private key
-        let wallet: LocalWallet = signer_private_key
-            .parse::<LocalWallet>()
-            .unwrap()
-            .with_chain_id(chain_id.as_u64());
+    //approve the spender only when the current allowance does not cover the amount
+    //the approve is mined before returning, the router has no entry point taking a permit for swaps or deposits
+    async fn ensure_allowance(
+        &self,
+        client: Arc<WalletClient>,
+        token: &W3Token,
+        spender: Address,
+        amount: U256,
+        approve_max: bool,
+    ) -> Result<Approval> {
+        let owner = client.address();
+        let allowance = self
+            .get_allowance(client.clone(), token, owner, spender)
+            .await?;
+        if allowance >= amount {
+            return Ok(Approval::Covered);
+        }
 
-        //combine both of those above to a client provider
-        let client = Arc::new(SignerMiddleware::new(
-            self.provider.clone(),
-            wallet.with_chain_id(chain_id.as_u64()),
-        ));
+        let approval_amount = match approve_max {
+            true => U256::MAX,
+            false => amount,
+        };
 
-        Ok(client)
+        let token_contract = self.establish_contract_erc20(client.clone(), token)?;
+        let approve_tx = token_contract.method::<_, H256>("approve", (spender, approval_amount))?;
+        self.simulate(client.as_ref(), &approve_tx.tx).await?;
+        let pending_approve_tx = approve_tx.send().await.map_err(send_error)?;
+        let approval_hash = format!("{:?}", pending_approve_tx.tx_hash());
+        if self.dry_run {
+            return Ok(Approval::Simulated(approval_hash));
+        }
+        self.confirm_approval(pending_approve_tx, &approval_hash)
+            .await?;
+        Ok(Approval::Approved(approval_hash))
     }
 
-    fn get_valid_timestamp(&self, future_millis: u128) -> u128 {
-        let start = SystemTime::now();
-        let since_epoch = start.duration_since(UNIX_EPOCH).unwrap();
-        let time_millis = since_epoch.as_millis().checked_add(future_millis).unwrap();
-        time_millis
+    fn establish_contract_permit<M: Middleware>(
+        &self,
+        client: Arc<M>,
+        token_address: Address,
+    ) -> Result<Contract<M>> {
+        //get abi contract, only the EIP-2612 functions
+        let abi: Abi = serde_json::from_str(CT_ERC20_PERMIT)?;
+
+        let contract = Contract::new(token_address, abi, client);
+        Ok(contract)
     }
-}
 
-#[async_trait]
-impl Web3Repository for InfuraRepository {
-    async fn transfer_token(
+    //sign an EIP-2612 permit against the domain separator published by the token
+    //none when the token does not support permits, then the caller falls back to approve
+    async fn sign_permit(
         &self,
-        sender_private_key: &str,
-        receipient_address: &str,
-        amount: &str,
-        chain: &str,
-    ) -> Result<CryptoTransactionResponseDTO> {
-        let contract_abi = ContractABI::map_token_contract(chain);
-        let client = self.establish_signer_wallet(sender_private_key).await?;
-        let signer_address = format!("{:?}", client.address());
-        let recipient = receipient_address
-            .parse::<Address>()
-            .map_err(|e| anyhow!("Invalid recipient address: {}", e))?;
+        client: Arc<WalletClient>,
+        token_address: Address,
+        spender: Address,
+        value: U256,
+        deadline: U256,
+    ) -> Result<Option<TypedSignature>> {
+        let owner = client.address();
+        let contract_permit = self.establish_contract_permit(client.clone(), token_address)?;
 
-        //check to know the contract is ETH or else
-        let rs = match contract_abi {
-            //if it is ETH so no need to create contract erc20
-            ContractABI::ETH => {
-                let tx = TransactionRequest::new()
-                    .to(recipient)
-                    .value(U256::from(utils::parse_ether(amount)?));
+        let domain_separator: [u8; 32] =
+            match contract_permit.method("DOMAIN_SEPARATOR", ())?.call().await {
+                std::result::Result::Ok(domain_separator) => domain_separator,
+                Err(error) if is_execution_error(&error) => return Ok(None),
+                Err(error) => return Err(upstream_error(error).into()),
+            };
+        let nonce: U256 = match contract_permit.method("nonces", owner)?.call().await {
+            std::result::Result::Ok(nonce) => nonce,
+            Err(error) if is_execution_error(&error) => return Ok(None),
+            Err(error) => return Err(upstream_error(error).into()),
+        };
 
-                let pending_tx = client.send_transaction(tx, None).await?;
-                let receipt = pending_tx.await?.unwrap_or_default();
+        let struct_hash = ethers::utils::keccak256(ethers::abi::encode(&[
+            ethers::abi::Token::FixedBytes(
+                ethers::utils::keccak256(
+                    "Permit(address owner,address spender,uint256 value,uint256 nonce,uint256 deadline)",
+                )
+                .to_vec(),
+            ),
+            ethers::abi::Token::Address(owner),
+            ethers::abi::Token::Address(spender),
+            ethers::abi::Token::Uint(value),
+            ethers::abi::Token::Uint(nonce),
+            ethers::abi::Token::Uint(deadline),
+        ]));
+        let signature =
+            TypedSignature::sign(client.inner().signer(), domain_separator, struct_hash)?;
+        Ok(Some(signature))
+    }
 
-                format!("{:?}", receipt.transaction_hash)
-            }
-            //if it is not ETH so must create erc20 contract
-            _ => {
-                let contract = self.establish_contract_erc20(client, contract_abi).await?;
-                let decimal_amount = self.parse_amount(contract.clone(), amount).await?;
+    //the router is the only contract the service approves, so it is the default spender
+    fn resolve_spender(&self, spender: Option<&str>) -> Result<Address> {
+        match spender {
+            Some(spender) => Ok(parse_address(spender, "spender")?),
+            None => Ok(self.router_address),
+        }
+    }
+
+    //tokens following the USDC (FiatToken) abi, used for EIP-3009 authorizations
+    fn establish_contract_usdc<M: Middleware>(
+        &self,
+        client: Arc<M>,
+        token: &W3Token,
+    ) -> Result<Contract<M>> {
+        //get abi contract
+        let abi: Abi = serde_json::from_str(CT_USDC)?;
+
+        //get contract address from the token registry
+        let contract_address = self.token_address(token)?;
 
-                let tx = contract
-                    .method::<(Address, U256), H256>("transfer", (recipient, decimal_amount))?;
+        let contract = Contract::new(contract_address, abi, client);
+        Ok(contract)
+    }
 
-                let pending_tx = tx.send().await?;
-                let receipt = pending_tx.await?.unwrap_or_default();
+    //the sender only signs an EIP-3009 authorization, the relayer wallet submits it and pays the gas
+    async fn relayed_transfer(
+        &self,
+        sender_wallet_id: &str,
+        recipient: Address,
+        token: &W3Token,
+        token_amount: &TokenAmount,
+    ) -> Result<(String, Address)> {
+        let relayer_wallet_id = self.relayer_wallet_id()?;
+        if token.is_native() {
+            return Err(DomainError::Validation(format!(
+                "{} cannot be transferred by a relayer",
+                token.symbol
+            ))
+            .into());
+        }
 
-                format!("{:?}", receipt.transaction_hash)
-            }
+        self.ensure_relayed_minimum(token, token_amount)?;
+
+        let sender = self.establish_signer_wallet(sender_wallet_id).await?;
+        let relayer = self.establish_signer_wallet(relayer_wallet_id).await?;
+        let contract_usdc = self.establish_contract_usdc(relayer.clone(), token)?;
+
+        let domain_separator: [u8; 32] =
+            match contract_usdc.method("DOMAIN_SEPARATOR", ())?.call().await {
+                std::result::Result::Ok(domain_separator) => domain_separator,
+                Err(error) if is_execution_error(&error) => {
+                    return Err(DomainError::Validation(format!(
+                        "{} does not support relayed transfers",
+                        token.symbol
+                    ))
+                    .into())
+                }
+                Err(error) => return Err(upstream_error(error).into()),
+            };
+
+        //every authorization carries a random nonce, the token rejects it once used
+        let mut nonce = [0u8; 32];
+        thread_rng().fill_bytes(&mut nonce);
+        let value = U256::from(token_amount.base_units());
+        let valid_after = U256::zero();
+        let valid_before = self.get_deadline(RELAYED_AUTHORIZATION_SECONDS)?;
+
+        let struct_hash = ethers::utils::keccak256(ethers::abi::encode(&[
+            ethers::abi::Token::FixedBytes(
+                ethers::utils::keccak256(
+                    "TransferWithAuthorization(address from,address to,uint256 value,uint256 validAfter,uint256 validBefore,bytes32 nonce)",
+                )
+                .to_vec(),
+            ),
+            ethers::abi::Token::Address(sender.address()),
+            ethers::abi::Token::Address(recipient),
+            ethers::abi::Token::Uint(value),
+            ethers::abi::Token::Uint(valid_after),
+            ethers::abi::Token::Uint(valid_before),
+            ethers::abi::Token::FixedBytes(nonce.to_vec()),
+        ]));
+        let signature =
+            TypedSignature::sign(sender.inner().signer(), domain_separator, struct_hash)?;
+
+        //transferWithAuthorization is overloaded, the v, r, s variant is picked by its selector
+        let selector = ethers::utils::id(
+            "transferWithAuthorization(address,address,uint256,uint256,uint256,bytes32,uint8,bytes32,bytes32)",
+        );
+        let tx = contract_usdc.method_hash::<_, ()>(
+            selector,
+            (
+                sender.address(),
+                recipient,
+                value,
+                valid_after,
+                valid_before,
+                nonce,
+                signature.v,
+                signature.r,
+                signature.s,
+            ),
+        )?;
+
+        //simulate first so a rejected authorization does not cost the relayer any gas
+        self.simulate(relayer.as_ref(), &tx.tx).await?;
+        let gas = tx.estimate_gas().await.map_err(send_error)?;
+        let estimate = self.cost_estimate(gas, U256::zero()).await?;
+        self.ensure_affordable(relayer.address(), &estimate).await?;
+
+        //like direct transfers, the status is followed with GET /crypto/transaction/{hash}
+        let pending_tx = tx.send().await.map_err(send_error)?;
+        Ok((format!("{:?}", pending_tx.tx_hash()), relayer.address()))
+    }
+
+    //the minimum is read with the decimals of the token being relayed
+    fn ensure_relayed_minimum(&self, token: &W3Token, token_amount: &TokenAmount) -> Result<()> {
+        let min_amount = token.parse_amount(&self.relayed_min_amount).map_err(|_| {
+            DomainError::Internal(format!(
+                "RELAYED_TRANSFER_MIN_AMOUNT is not a valid {} amount",
+                token.symbol
+            ))
+        })?;
+        if token_amount.base_units() < min_amount.base_units() {
+            return Err(DomainError::Validation(format!(
+                "Relayed transfers of {} must be at least {}",
+                token.symbol,
+                min_amount.format()
+            ))
+            .into());
+        }
+        Ok(())
+    }
+
+    fn relayer_wallet_id(&self) -> Result<&str> {
+        let relayer_wallet_id = self.relayer_wallet_id.as_deref().ok_or_else(|| {
+            DomainError::Validation("Relayed transfers are not enabled".to_string())
+        })?;
+        Ok(relayer_wallet_id)
+    }
+
+    //deposit native ETH into WETH, or withdraw it back
+    async fn convert_native(
+        &self,
+        wallet_id: &str,
+        amount: &str,
+        wrap: bool,
+    ) -> Result<CryptoWrapResponseDTO> {
+        let wrapped_native = self.resolve_token("WETH").await?;
+        let client = self.establish_signer_wallet(wallet_id).await?;
+        let signer_address = client.address();
+
+        //WETH has the decimals of ETH, so one amount is valid on both sides
+        let token_amount = self.parse_amount(&wrapped_native, amount)?;
+        let decimal_amount = U256::from(token_amount.base_units());
+
+        let contract_weth = self.establish_contract_weth(client.clone(), &wrapped_native)?;
+        let tx = match wrap {
+            true => contract_weth
+                .method::<_, H256>("deposit", ())?
+                .value(decimal_amount),
+            false => contract_weth.method::<_, H256>("withdraw", decimal_amount)?,
         };
 
-        let result = CryptoTransactionResponseDTO {
-            transaction_hash: rs,
-            sender_address: signer_address,
-            receipient_address: receipient_address.to_string(),
-            amount: amount.to_string(),
+        //same as swaps, return the hash without waiting for the receipt
+        self.simulate(client.as_ref(), &tx.tx).await?;
+        let pending_tx = tx.send().await.map_err(send_error)?;
+        let tx_hash = format!("{:?}", pending_tx.tx_hash());
+        let (from_token, to_token) = match wrap {
+            true => ("ETH", "WETH"),
+            false => ("WETH", "ETH"),
+        };
+        let response_dto = CryptoWrapResponseDTO {
+            transaction_hash: tx_hash,
+            address: format!("{:?}", signer_address),
+            amount: token_amount.to_dto(),
+            from_token: from_token.to_string(),
+            to_token: to_token.to_string(),
             timestamp: Utc::now().timestamp().to_string(),
         };
-        Ok(result)
+        Ok(response_dto)
     }
 
-    async fn get_balance(&self, signer_private_key: &str, chain: &str) -> Result<String> {
-        let client = self.establish_signer_wallet(signer_private_key).await?;
-        let contract = ContractABI::map_token_contract(chain);
-        let address = client.address();
+    fn establish_contract_factory<M: Middleware>(
+        &self,
+        client: Arc<M>,
+        factory_address: Address,
+    ) -> Result<Contract<M>> {
+        //get abi contract
+        let abi: Abi = serde_json::from_str(CT_FACTORY02)?;
 
-        match contract {
-            ContractABI::ETH => {
-                let balance = self.provider.clone().get_balance(address, None).await?;
-                let formatted_balance = utils::format_units(balance, 18)?;
-                Ok(formatted_balance.to_string())
-            }
-            _ => {
-                let token_contract = self.establish_contract_erc20(client, contract).await?;
-                // Retrieve decimals of the contract token for convert balance
-                let decimals: u8 = token_contract.method("decimals", ())?.call().await?;
-                //retrieve and convert token balance by decimals
-                let balance: U256 = token_contract.method("balanceOf", address)?.call().await?;
-                let formatted_balance = utils::format_units(balance, decimals as i32)?;
-                Ok(formatted_balance.to_string())
-            }
-        }
+        //the factory address is read from the router, so it always matches ROUTER02
+        let contract = Contract::new(factory_address, abi, client);
+        Ok(contract)
     }
 
-    async fn get_wallet(&self, signer_private_key: &str) -> Result<String> {
-        //do it like establishing a client wallet
-        let chain_id = self.provider.clone().get_chainid().await?;
-        let wallet = signer_private_key
-            .parse::<LocalWallet>()?
-            .with_chain_id(chain_id.as_u64());
+    fn establish_contract_pair<M: Middleware>(
+        &self,
+        client: Arc<M>,
+        pair_address: Address,
+    ) -> Result<Contract<M>> {
+        //get abi contract
+        let abi: Abi = serde_json::from_str(CT_PAIR)?;
 
-        //just return the wallet address as string
-        let address = format!("{:?}", wallet.address());
-        Ok(address)
+        let contract = Contract::new(pair_address, abi, client);
+        Ok(contract)
     }
 
-    async fn create_wallet(&self) -> Result<(String, String)> {
-        let wallet = LocalWallet::new(&mut thread_rng());
-        let private_key = hex::encode(wallet.signer().to_bytes());
-        let address = format!("{:?}", wallet.address());
+    //apply the defaults and reject values above the server maximums
+    fn resolve_swap_settings(&self, options: &SwapOptionsDTO) -> Result<SwapSettings> {
+        let mode = match &options.mode {
+            Some(mode) => SwapMode::from_mode(mode).ok_or_else(|| {
+                DomainError::Validation(format!(
+                    "Invalid swap mode {}, expected exact_input or exact_output",
+                    mode
+                ))
+            })?,
+            None => SwapMode::ExactInput,
+        };
+
+        let slippage_bps = options.slippage_bps.unwrap_or(DEFAULT_SLIPPAGE_BPS);
+        if slippage_bps > self.max_slippage_bps {
+            return Err(DomainError::Validation(format!(
+                "slippage_bps must be at most {}",
+                self.max_slippage_bps
+            ))
+            .into());
+        }
+
+        let deadline_seconds = options.deadline_seconds.unwrap_or(DEFAULT_DEADLINE_SECONDS);
+        if deadline_seconds == 0 || deadline_seconds > self.max_deadline_seconds {
+            return Err(DomainError::Validation(format!(
+                "deadline_seconds must be between 1 and {}",
+                self.max_deadline_seconds
+            ))
+            .into());
+        }
+
+        let recipient = match &options.recipient {
+            Some(recipient) => Some(parse_address(recipient, "recipient")?),
+            None => None,
+        };
 
-        Ok((address, private_key))
+        Ok(SwapSettings {
+            mode,
+            slippage_bps,
+            deadline_seconds,
+            approve_max: options.approve_max.unwrap_or(false),
+            recipient,
+        })
     }
 
-    async fn swap(
+    //resolve both tokens, the path and the expected amounts of a swap without sending anything
+    async fn plan_swap<M: Middleware>(
         &self,
+        contract_router: &Contract<M>,
         from_token: &str,
         to_token: &str,
         amount: &str,
-        signer_private_key: &str,
-    ) -> Result<CryptoSwapResponseDTO> {
-        // Establish client and signer
-        let client = self.establish_signer_wallet(signer_private_key).await?;
-        let signer_address = client.address();
+        settings: &SwapSettings,
+    ) -> Result<SwapPlan> {
+        // Map from-token and destination token; if it's ETH, use the WETH pools
+        // The router takes and pays native ETH itself, so ETH and WETH are not the same side of a swap
+        let (from_detect, from_native) = self.resolve_swap_token(from_token).await?;
+        let (destination_detect, to_native) = self.resolve_swap_token(to_token).await?;
+        if from_detect.address == destination_detect.address {
+            return Err(DomainError::Validation(format!(
+                "Cannot swap {} to {}, use /crypto/wrap or /crypto/unwrap to convert ETH and WETH",
+                from_token.to_uppercase(),
+                to_token.to_uppercase()
+            ))
+            .into());
+        }
 
-        // Get router contract
-        let contract_router = self
-            .establish_contract_router(client.clone(), ContractABI::ROUTER02)
+        // Parse the user amount into base units with the registered decimals; for example, "10" USDC will be 10*10^6
+        // It is the input for an exact-input swap and the output for an exact-output swap
+        let fixed_token = match settings.mode {
+            SwapMode::ExactInput => &from_detect,
+            SwapMode::ExactOutput => &destination_detect,
+        };
+        let fixed_amount = self.parse_amount(fixed_token, amount)?;
+
+        // Pick the path giving the best quote
+        let (route, path, quoted) = self
+            .find_route(
+                contract_router,
+                &from_detect,
+                &destination_detect,
+                U256::from(fixed_amount.base_units()),
+                settings.mode,
+            )
             .await?;
-        let router_address = contract_router.address();
 
-        // Map from-token; if it's ETH, use WETH
-        let mut from_detect = ContractABI::map_token_contract(from_token);
-        if from_detect == ContractABI::ETH {
-            from_detect = ContractABI::WETH;
+        // A taxed token without a known fee would get a minimum output above what the recipient receives
+        if let Some(token) = route
+            .iter()
+            .find(|token| token.fee_on_transfer && token.transfer_fee_bps <= 0)
+        {
+            return Err(DomainError::Validation(format!(
+                "{} is fee-on-transfer but has no transfer_fee_bps registered",
+                token.symbol
+            ))
+            .into());
         }
-        let from_contract = self
-            .establish_contract_erc20(client.clone(), from_detect)
-            .await?;
-        let from_address = from_contract.address();
-
-        // Map destination token; if it's ETH, use WETH
-        let mut destination_detect = ContractABI::map_token_contract(to_token);
-        if destination_detect == ContractABI::ETH {
-            destination_detect = ContractABI::WETH;
-        }
-        let destination_address =
-            ContractABI::get_contract_address(&destination_detect).parse::<Address>()?;
-
-        // Process the amount
-        // Get decimals from the from-contract
-        let decimals: u8 = from_contract.method("decimals", ())?.call().await?;
-        // Parse the input amount into base units; for example, "10" USDC will be 10*10^6
-        let approval_amount: U256 =
-            U256::from_dec_str(&parse_units(amount, decimals as i32)?.to_string()).unwrap();
-
-        // Get expected output using getAmountsOut.
-        // (Make sure the token order is correct: for swapping ETH→USDC, the path is [WETH, USDC])
-        let range_expected: Vec<U256> = contract_router
-            .method(
-                "getAmountsOut",
-                (approval_amount, vec![from_address, destination_address]),
-            )?
-            .call()
-            .await?;
-        let expected_amount = range_expected
-            .last()
-            .ok_or_else(|| anyhow!("No expected amount"))?;
-        // Apply a 5% slippage tolerance
-        let amount_out_min = expected_amount * U256::from(95) / U256::from(100);
 
-        // If swapping tokens , approve the router to spend your tokens.
-        let approve_tx =
-            from_contract.method::<_, H256>("approve", (router_address, approval_amount))?;
-        let pending_approve_tx = approve_tx.send().await?;
-        pending_approve_tx.await?; // Wait for approval to be mined
+        // Determine which swap method to use
+        let fee_on_transfer = route.iter().any(|token| token.fee_on_transfer);
+        let method =
+            SwapMethod::map_swap_method(from_native, to_native, settings.mode, fee_on_transfer)?;
 
-        // Set a deadline timestamp
-        let valid_time = self.get_valid_timestamp(300_000);
-        let u256_timestamp = U256::from(valid_time);
+        // Apply the slippage tolerance on the side that is not fixed
+        let slippage_bps = settings.slippage_bps;
+        let (amount_in, amount_in_max, expected_out, amount_out_min) = match settings.mode {
+            SwapMode::ExactInput => {
+                // The router quote ignores taxes, every taxed token on the path takes its fee once
+                let expected_out = route.iter().filter(|token| token.fee_on_transfer).fold(
+                    quoted,
+                    |amount, token| {
+                        let fee_bps = token.transfer_fee_bps.clamp(0, 9_999) as u64;
+                        amount * U256::from(10_000 - fee_bps) / U256::from(10_000)
+                    },
+                );
+                let amount_out_min =
+                    expected_out * U256::from(10_000 - slippage_bps) / U256::from(10_000);
+                let amount_in_max = U256::from(fixed_amount.base_units());
+                (fixed_amount, amount_in_max, expected_out, amount_out_min)
+            }
+            SwapMode::ExactOutput => {
+                let amount_in_max = quoted
+                    .checked_mul(U256::from(10_000 + slippage_bps))
+                    .ok_or_else(|| DomainError::Internal("Amount overflow".to_string()))?
+                    / U256::from(10_000);
+                let amount_in = self.to_token_amount(&from_detect, quoted)?;
+                let amount_out = U256::from(fixed_amount.base_units());
+                (amount_in, amount_in_max, amount_out, amount_out)
+            }
+        };
 
-        // Determine which swap method to use
-        let swap_method = SwapMethod::map_swap_method(from_detect, destination_detect)?;
+        Ok(SwapPlan {
+            from_token: from_detect,
+            to_token: destination_detect,
+            route,
+            path,
+            method,
+            mode: settings.mode,
+            fee_on_transfer,
+            amount_in,
+            amount_in_max,
+            quoted_out: match settings.mode {
+                SwapMode::ExactInput => quoted,
+                SwapMode::ExactOutput => expected_out,
+            },
+            expected_out,
+            amount_out_min,
+            slippage_bps,
+        })
+    }
 
-        // Build the swap transaction.
-        // For ETH-to-token swap (swapExactETHForTokens) -> need attach the ETH value.
-        let swap_tx = match swap_method {
-            SwapMethod::SwapExactETHForTokens => {
-                contract_router
-                    .method::<_, H256>(
-                        &SwapMethod::to_string(&swap_method),
-                        (
-                            amount_out_min,
-                            vec![from_address, destination_address],
-                            signer_address,
-                            u256_timestamp,
-                        ),
-                    )?
-                    .value(approval_amount) // Here, approval_amount is the ETH amount in wei
+    //try the direct path and the paths through each registered intermediate, keep the best quote:
+    //the highest output for an exact input, the lowest input for an exact output
+    async fn find_route<M: Middleware>(
+        &self,
+        contract_router: &Contract<M>,
+        from_token: &W3Token,
+        to_token: &W3Token,
+        fixed_amount: U256,
+        mode: SwapMode,
+    ) -> Result<(Vec<W3Token>, Vec<Address>, U256)> {
+        let mut candidates = vec![vec![from_token.clone(), to_token.clone()]];
+        for symbol in ROUTE_INTERMEDIATES {
+            if let Some(intermediate) = self.tokens.get_token(symbol, &self.network).await? {
+                if intermediate.address != from_token.address
+                    && intermediate.address != to_token.address
+                {
+                    candidates.push(vec![from_token.clone(), intermediate, to_token.clone()]);
+                }
             }
-            _ => {
+        }
+
+        let mut best: Option<(Vec<W3Token>, Vec<Address>, U256)> = None;
+        for route in candidates {
+            let path = route
+                .iter()
+                .map(|token| self.token_address(token))
+                .collect::<Result<Vec<Address>>>()?;
+
+            // Get the quote using getAmountsOut or getAmountsIn.
+            // (Make sure the token order is correct: for swapping ETH→USDC, the path is [WETH, USDC])
+            let quote_method = match mode {
+                SwapMode::ExactInput => "getAmountsOut",
+                SwapMode::ExactOutput => "getAmountsIn",
+            };
+            let amounts: Vec<U256> = match contract_router
+                .method(quote_method, (fixed_amount, path.clone()))?
+                .call()
+                .await
+            {
+                std::result::Result::Ok(amounts) => amounts,
+                //the router reverts when a pool of the path does not exist or is empty
+                Err(error) if is_execution_error(&error) => continue,
+                Err(error) => return Err(upstream_error(error).into()),
+            };
+            let quoted = match mode {
+                SwapMode::ExactInput => amounts.last(),
+                SwapMode::ExactOutput => amounts.first(),
+            };
+            let quoted = match quoted {
+                Some(quoted) if !quoted.is_zero() => *quoted,
+                _ => continue,
+            };
+
+            let is_better = match (&best, mode) {
+                (None, _) => true,
+                (Some((_, _, best_quoted)), SwapMode::ExactInput) => quoted > *best_quoted,
+                (Some((_, _, best_quoted)), SwapMode::ExactOutput) => quoted < *best_quoted,
+            };
+            if is_better {
+                best = Some((route, path, quoted));
+            }
+        }
+
+        let best = best.ok_or_else(|| {
+            DomainError::Validation(format!(
+                "No swap route found from {} to {}",
+                from_token.symbol, to_token.symbol
+            ))
+        })?;
+        Ok(best)
+    }
+
+    //build the router call of a plan, swaps paying with ETH attach the ETH value instead of an amountIn
+    fn build_swap_call<M: Middleware>(
+        &self,
+        contract_router: &Contract<M>,
+        plan: &SwapPlan,
+        recipient: Address,
+        deadline: U256,
+    ) -> Result<ContractCall<M, H256>> {
+        let amount_in = U256::from(plan.amount_in.base_units());
+        let method = plan.method.to_string();
+        let swap_tx = match plan.method {
+            SwapMethod::SwapExactETHForTokens
+            | SwapMethod::SwapExactETHForTokensSupportingFeeOnTransferTokens => contract_router
+                .method::<_, H256>(
+                    &method,
+                    (plan.amount_out_min, plan.path.clone(), recipient, deadline),
+                )?
+                .value(amount_in),
+            SwapMethod::SwapExactTokensForETH
+            | SwapMethod::SwapExactTokensForTokens
+            | SwapMethod::SwapExactTokensForETHSupportingFeeOnTransferTokens
+            | SwapMethod::SwapExactTokensForTokensSupportingFeeOnTransferTokens => {
                 contract_router.method::<_, H256>(
-                    &SwapMethod::to_string(&swap_method),
+                    &method,
                     (
-                        approval_amount, // amountIn for token-to-ETH or token-to-token swaps
-                        amount_out_min,  // minimum acceptable output
-                        vec![from_address, destination_address],
-                        signer_address,
-                        u256_timestamp,
+                        amount_in,           // amountIn for token-to-ETH or token-to-token swaps
+                        plan.amount_out_min, // minimum acceptable output
+                        plan.path.clone(),
+                        recipient,
+                        deadline,
+                    ),
+                )?
+            }
+            // The router refunds the ETH it does not need
+            SwapMethod::SwapETHForExactTokens => contract_router
+                .method::<_, H256>(
+                    &method,
+                    (plan.expected_out, plan.path.clone(), recipient, deadline),
+                )?
+                .value(plan.amount_in_max),
+            SwapMethod::SwapTokensForExactETH | SwapMethod::SwapTokensForExactTokens => {
+                contract_router.method::<_, H256>(
+                    &method,
+                    (
+                        plan.expected_out,  // exact amountOut
+                        plan.amount_in_max, // maximum input the router may take
+                        plan.path.clone(),
+                        recipient,
+                        deadline,
                     ),
                 )?
             }
         };
+        Ok(swap_tx)
+    }
 
-        // IMPORTANT: To avoid Lambda timeout (30s), don't wait for full transaction receipt.
-        // Instead, send the transaction and return the transaction hash.
-        let pending_swap_tx = swap_tx.send().await?;
-        let tx_hash = format!("{:?}", pending_swap_tx.tx_hash());
-        let response_dto = CryptoSwapResponseDTO {
-            transaction_hash: tx_hash,
-            address: format!("{:?}", signer_address),
-            amount_in: amount.to_string(),
-            from_token: from_token.to_string(),
-            to_token: to_token.to_string(),
-            timestamp: Utc::now().timestamp().to_string(),
-        };
-        Ok(response_dto)
+    //only reported for an exact output, where the input is not fixed
+    fn amount_in_max(&self, plan: &SwapPlan) -> Result<Option<AmountDTO>> {
+        match plan.mode {
+            SwapMode::ExactInput => Ok(None),
+            SwapMode::ExactOutput => Ok(Some(
+                self.to_token_amount(&plan.from_token, plan.amount_in_max)?
+                    .to_dto(),
+            )),
+        }
+    }
+
+    //the measured fee is kept when a single taxed token is on the path, so the next quotes include it
+    async fn record_transfer_fee(&self, plan: &SwapPlan, received: U256) {
+        let taxed_tokens = plan.taxed_tokens();
+        if taxed_tokens.len() != 1 || plan.quoted_out.is_zero() || received > plan.quoted_out {
+            return;
+        }
+        let fee_bps = (plan.quoted_out - received) * U256::from(10_000) / plan.quoted_out;
+        let token = taxed_tokens[0];
+        if let Err(error) = self
+            .tokens
+            .update_transfer_fee(&token.symbol, &self.network, fee_bps.as_u32() as i32)
+            .await
+        {
+            tracing::error!(
+                "failed to record the transfer fee of {} : {:?}",
+                token.symbol,
+                error
+            );
+        }
+    }
+
+    //input really spent by a mined swap: the first transfer of the input token moves it into the first pair
+    fn spent_amount(&self, plan: &SwapPlan, receipt: &TransactionReceipt) -> Option<U256> {
+        let transfer_topic = H256::from(ethers::utils::keccak256(
+            "Transfer(address,address,uint256)",
+        ));
+        receipt
+            .logs
+            .iter()
+            .find(|log| log.address == plan.path[0] && log.topics.first() == Some(&transfer_topic))
+            .map(|log| U256::from_big_endian(&log.data))
+    }
+
+    //price impact in basis points, comparing the quoted output with the output at the current pair prices
+    async fn price_impact_bps<M: Middleware>(
+        &self,
+        client: Arc<M>,
+        contract_router: &Contract<M>,
+        plan: &SwapPlan,
+    ) -> Result<u32> {
+        let factory_address: Address = contract_router
+            .method("factory", ())?
+            .call()
+            .await
+            .map_err(upstream_error)?;
+        let contract_factory = self.establish_contract_factory(client.clone(), factory_address)?;
+
+        let overflow = || DomainError::Internal("Price impact overflow".to_string());
+        let mut ideal_out = U256::from(plan.amount_in.base_units());
+        for hop in plan.path.windows(2) {
+            let pair_address: Address = contract_factory
+                .method("getPair", (hop[0], hop[1]))?
+                .call()
+                .await
+                .map_err(upstream_error)?;
+            if pair_address.is_zero() {
+                return Err(
+                    DomainError::Validation("No liquidity pool for this pair".to_string()).into(),
+                );
+            }
+
+            let contract_pair = self.establish_contract_pair(client.clone(), pair_address)?;
+            let (reserve0, reserve1, _): (U256, U256, u32) = contract_pair
+                .method("getReserves", ())?
+                .call()
+                .await
+                .map_err(upstream_error)?;
+            let token0: Address = contract_pair
+                .method("token0", ())?
+                .call()
+                .await
+                .map_err(upstream_error)?;
+            let (reserve_in, reserve_out) = match token0 == hop[0] {
+                true => (reserve0, reserve1),
+                false => (reserve1, reserve0),
+            };
+            if reserve_in.is_zero() {
+                return Err(
+                    DomainError::Validation("No liquidity pool for this pair".to_string()).into(),
+                );
+            }
+
+            //output at the spot price, the pair fee is kept so only the size of the trade counts
+            ideal_out = ideal_out
+                .checked_mul(reserve_out)
+                .and_then(|value| value.checked_mul(U256::from(PAIR_FEE_NUMERATOR)))
+                .ok_or_else(overflow)?
+                / reserve_in
+                    .checked_mul(U256::from(PAIR_FEE_DENOMINATOR))
+                    .ok_or_else(overflow)?;
+        }
+
+        if ideal_out.is_zero() || plan.quoted_out >= ideal_out {
+            return Ok(0);
+        }
+        let impact = (ideal_out - plan.quoted_out) * U256::from(10_000) / ideal_out;
+        Ok(impact.as_u32())
+    }
+
+    //gas of the approval and the swap, simulated from the wallet when possible
+    async fn estimate_swap_gas<M: Middleware>(
+        &self,
+        contract_router: &Contract<M>,
+        plan: &SwapPlan,
+        wallet_id: Option<&str>,
+        settings: &SwapSettings,
+    ) -> Result<U256> {
+        let hops = plan.path.len().saturating_sub(2) as u64;
+        let fallback = U256::from(SWAP_GAS_FALLBACK + SWAP_HOP_GAS_FALLBACK * hops);
+        let mut needs_approval = !plan.method.pays_in_eth();
+        let swap_gas = match wallet_id {
+            Some(wallet_id) => {
+                let sender = self.get_wallet_address(wallet_id).await?;
+                if needs_approval {
+                    let allowance = self
+                        .get_allowance(
+                            Arc::new(self.provider.clone()),
+                            &plan.from_token,
+                            sender,
+                            contract_router.address(),
+                        )
+                        .await?;
+                    needs_approval = allowance < plan.amount_in_max;
+                }
+                let recipient = settings.recipient.unwrap_or(sender);
+                let deadline = self.get_deadline(settings.deadline_seconds)?;
+                self.build_swap_call(contract_router, plan, recipient, deadline)?
+                    .from(sender)
+                    .estimate_gas()
+                    .await
+                    .unwrap_or(fallback)
+            }
+            None => fallback,
+        };
+
+        //erc20 inputs approve the router first, unless the allowance already covers the swap
+        match needs_approval {
+            true => Ok(swap_gas + U256::from(APPROVE_GAS_FALLBACK)),
+            false => Ok(swap_gas),
+        }
+    }
+
+    //liquidity takes the slippage, deadline and approval settings of swaps
+    fn resolve_liquidity_settings(&self, options: &LiquidityOptionsDTO) -> Result<SwapSettings> {
+        self.resolve_swap_settings(&SwapOptionsDTO {
+            slippage_bps: options.slippage_bps,
+            deadline_seconds: options.deadline_seconds,
+            approve_max: options.approve_max,
+            ..Default::default()
+        })
+    }
+
+    //both sides of a pool, native ETH is deposited into the WETH pools like for swaps
+    async fn resolve_liquidity_tokens(
+        &self,
+        token_a: &str,
+        token_b: &str,
+    ) -> Result<((W3Token, bool), (W3Token, bool))> {
+        let side_a = self.resolve_swap_token(token_a).await?;
+        let side_b = self.resolve_swap_token(token_b).await?;
+        if side_a.0.address == side_b.0.address {
+            return Err(DomainError::Validation(format!(
+                "{} and {} are not a liquidity pair",
+                token_a.to_uppercase(),
+                token_b.to_uppercase()
+            ))
+            .into());
+        }
+        Ok((side_a, side_b))
+    }
+
+    async fn get_pair_address<M: Middleware>(
+        &self,
+        client: Arc<M>,
+        contract_router: &Contract<M>,
+        token_a: Address,
+        token_b: Address,
+    ) -> Result<Address> {
+        let factory_address: Address = contract_router
+            .method("factory", ())?
+            .call()
+            .await
+            .map_err(upstream_error)?;
+        let contract_factory = self.establish_contract_factory(client, factory_address)?;
+        let pair_address: Address = contract_factory
+            .method("getPair", (token_a, token_b))?
+            .call()
+            .await
+            .map_err(upstream_error)?;
+        Ok(pair_address)
+    }
+
+    //reserves and LP supply of the pool, none when the factory has no pair for the tokens
+    async fn pair_state<M: Middleware>(
+        &self,
+        client: Arc<M>,
+        contract_router: &Contract<M>,
+        token_a: Address,
+        token_b: Address,
+    ) -> Result<Option<PairState>> {
+        let pair_address = self
+            .get_pair_address(client.clone(), contract_router, token_a, token_b)
+            .await?;
+        if pair_address.is_zero() {
+            return Ok(None);
+        }
+
+        let contract_pair = self.establish_contract_pair(client, pair_address)?;
+        let (reserve0, reserve1, _): (U256, U256, u32) = contract_pair
+            .method("getReserves", ())?
+            .call()
+            .await
+            .map_err(upstream_error)?;
+        let total_supply: U256 = contract_pair
+            .method("totalSupply", ())?
+            .call()
+            .await
+            .map_err(upstream_error)?;
+        //the factory sorts the tokens of a pair, token0 is the lower address
+        let (reserve_a, reserve_b) = match token_a < token_b {
+            true => (reserve0, reserve1),
+            false => (reserve1, reserve0),
+        };
+        Ok(Some(PairState {
+            address: pair_address,
+            reserve_a,
+            reserve_b,
+            total_supply,
+        }))
+    }
+
+    //LP token amount, always expressed with 18 decimals
+    fn to_lp_amount(&self, base_units: U256) -> Result<TokenAmount> {
+        let base_units = u128::try_from(base_units)
+            .map_err(|_| DomainError::Internal("LP amount exceeds supported range".to_string()))?;
+        Ok(TokenAmount::from_base_units(base_units, LP_DECIMALS))
+    }
+
+    //amounts moved by a mined liquidity call, read from the Mint or Burn event of the pair
+    fn pair_event_amounts(
+        &self,
+        receipt: &TransactionReceipt,
+        pair_address: Address,
+        event: &str,
+        a_is_token0: bool,
+    ) -> Option<(U256, U256)> {
+        let event_topic = H256::from(ethers::utils::keccak256(event));
+        let log = receipt
+            .logs
+            .iter()
+            .find(|log| log.address == pair_address && log.topics.first() == Some(&event_topic))?;
+        if log.data.len() < 64 {
+            return None;
+        }
+        let amount0 = U256::from_big_endian(&log.data[..32]);
+        let amount1 = U256::from_big_endian(&log.data[32..64]);
+        match a_is_token0 {
+            true => Some((amount0, amount1)),
+            false => Some((amount1, amount0)),
+        }
+    }
+
+    //LP tokens minted to the owner by a mined deposit
+    fn minted_liquidity(
+        &self,
+        receipt: &TransactionReceipt,
+        pair_address: Address,
+        owner: Address,
+    ) -> Option<U256> {
+        let transfer_topic = H256::from(ethers::utils::keccak256(
+            "Transfer(address,address,uint256)",
+        ));
+        receipt
+            .logs
+            .iter()
+            .find(|log| {
+                log.address == pair_address
+                    && log.topics.len() == 3
+                    && log.topics[0] == transfer_topic
+                    && log.topics[1] == H256::zero()
+                    && log.topics[2] == H256::from(owner)
+            })
+            .map(|log| U256::from_big_endian(&log.data))
+    }
+
+    //receipt of a sent transaction, none when it is not mined within RECEIPT_TIMEOUT_SECONDS
+    async fn await_receipt(
+        &self,
+        pending_tx: PendingTransaction<'_, Http>,
+        kind: &str,
+    ) -> Result<Option<TransactionReceipt>> {
+        let pending_tx = pending_tx.interval(Duration::from_secs(RECEIPT_POLL_SECONDS));
+        let receipt =
+            match tokio::time::timeout(Duration::from_secs(RECEIPT_TIMEOUT_SECONDS), pending_tx)
+                .await
+            {
+                std::result::Result::Ok(receipt) => receipt.map_err(upstream_error)?,
+                Err(_) => return Ok(None),
+            };
+        let receipt = receipt
+            .ok_or_else(|| DomainError::Upstream(format!("{} transaction was dropped", kind)))?;
+        Ok(Some(receipt))
+    }
+
+    //the call behind an approval can only be sent once the allowance is on chain
+    async fn confirm_approval(
+        &self,
+        pending_tx: PendingTransaction<'_, Http>,
+        approval_hash: &str,
+    ) -> Result<()> {
+        match self.await_receipt(pending_tx, "Approval").await? {
+            Some(_) => Ok(()),
+            None => Err(DomainError::Upstream(format!(
+                "Approval {} is not mined yet, retry once it is",
+                approval_hash
+            ))
+            .into()),
+        }
+    }
+
+    //liquidity calls wait for their receipt, the amounts they moved are only known once mined
+    //none in dry run or when it is not mined in time, the callers then keep their planned amounts
+    async fn confirm_liquidity_receipt(
+        &self,
+        pending_tx: PendingTransaction<'_, Http>,
+        tx_hash: &str,
+    ) -> Result<Option<TransactionReceipt>> {
+        if self.dry_run {
+            return Ok(None);
+        }
+        let receipt = match self.await_receipt(pending_tx, "Liquidity").await? {
+            Some(receipt) => receipt,
+            None => return Ok(None),
+        };
+        if receipt.status != Some(U64::from(1)) {
+            return Err(DomainError::Upstream(format!(
+                "Liquidity transaction {} reverted",
+                tx_hash
+            ))
+            .into());
+        }
+        Ok(Some(receipt))
+    }
+
+    fn establish_contract_multicall<M: Middleware>(&self, client: Arc<M>) -> Result<Contract<M>> {
+        //get abi contract
+        let abi: Abi = serde_json::from_str(CT_MULTICALL3)?;
+
+        let contract = Contract::new(self.multicall_address, abi, client);
+        Ok(contract)
+    }
+
+    //one aggregate3 round trip, a failed sub call comes back as none instead of failing the batch
+    async fn aggregate<M: Middleware>(
+        &self,
+        contract_multicall: &Contract<M>,
+        calls: Vec<(Address, Bytes)>,
+    ) -> Result<Vec<Option<Bytes>>> {
+        if calls.is_empty() {
+            return Ok(Vec::new());
+        }
+        let calls: Vec<(Address, bool, Bytes)> = calls
+            .into_iter()
+            .map(|(target, call_data)| (target, true, call_data))
+            .collect();
+        let results: Vec<(bool, Bytes)> = contract_multicall
+            .method("aggregate3", calls)?
+            .call()
+            .await
+            .map_err(upstream_error)?;
+        Ok(results
+            .into_iter()
+            .map(|(success, return_data)| success.then_some(return_data))
+            .collect())
+    }
+
+    //value of each balance in USDC base units, priced on the router with one token of each kind
+    //every direct and WETH route is quoted in the same aggregate call, the best one is kept
+    async fn valuate_balances<M: Middleware>(
+        &self,
+        contract_multicall: &Contract<M>,
+        balances: &[(W3Token, U256)],
+    ) -> Result<Option<(W3Token, Vec<Option<U256>>)>> {
+        let valuation_token = match self
+            .tokens
+            .get_token(VALUATION_TOKEN, &self.network)
+            .await?
+        {
+            Some(token) => token,
+            None => return Ok(None),
+        };
+        let valuation_address = self.token_address(&valuation_token)?;
+        let wrapped_native = self.tokens.get_token("WETH", &self.network).await?;
+        let router_abi: Abi = serde_json::from_str(CT_ROUTER02)?;
+        let contract_router = BaseContract::from(router_abi);
+
+        //the quotes use the WETH pools for the native coin, like swaps do
+        let priced_token = |token: &W3Token| -> Option<W3Token> {
+            match token.is_native() {
+                true => wrapped_native.clone(),
+                false => Some(token.clone()),
+            }
+        };
+
+        let mut calls = Vec::<(Address, Bytes)>::new();
+        let mut quoted_indexes = Vec::<usize>::new();
+        for (index, (token, balance)) in balances.iter().enumerate() {
+            let priced = match priced_token(token) {
+                Some(priced) => priced,
+                None => continue,
+            };
+            if balance.is_zero() || priced.address == valuation_token.address {
+                continue;
+            }
+            let priced_address = match self.token_address(&priced) {
+                std::result::Result::Ok(address) => address,
+                Err(_) => continue,
+            };
+            let mut paths = vec![vec![priced_address, valuation_address]];
+            if let Some(wrapped_native) = &wrapped_native {
+                if wrapped_native.address != priced.address {
+                    paths.push(vec![
+                        priced_address,
+                        self.token_address(wrapped_native)?,
+                        valuation_address,
+                    ]);
+                }
+            }
+            let one_token = U256::exp10(priced.decimals as usize);
+            for path in paths {
+                calls.push((
+                    self.router_address,
+                    contract_router.encode("getAmountsOut", (one_token, path))?,
+                ));
+                quoted_indexes.push(index);
+            }
+        }
+
+        let results = self.aggregate(contract_multicall, calls).await?;
+        let mut unit_prices: Vec<Option<U256>> = vec![None; balances.len()];
+        for (index, result) in quoted_indexes.into_iter().zip(results) {
+            let quoted = result
+                .and_then(|data| {
+                    contract_router
+                        .decode_output::<Vec<U256>, _>("getAmountsOut", data)
+                        .ok()
+                })
+                .and_then(|amounts| amounts.last().copied());
+            if let Some(quoted) = quoted {
+                if unit_prices[index].is_none_or(|best| quoted > best) {
+                    unit_prices[index] = Some(quoted);
+                }
+            }
+        }
+
+        let values = balances
+            .iter()
+            .zip(unit_prices)
+            .map(|((token, balance), unit_price)| {
+                let priced = priced_token(token)?;
+                if balance.is_zero() {
+                    return Some(U256::zero());
+                }
+                if priced.address == valuation_token.address {
+                    return Some(*balance);
+                }
+                //a huge balance, of a spam token for example, is left without a value instead of overflowing
+                unit_price.and_then(|unit_price| {
+                    mul_div(*balance, unit_price, U256::exp10(priced.decimals as usize))
+                })
+            })
+            .collect();
+        Ok(Some((valuation_token, values)))
+    }
+
+    //the native coin registered on the network, if any
+    async fn native_token(&self) -> Result<Option<W3Token>> {
+        let tokens = self.tokens.list_tokens().await?;
+        Ok(tokens
+            .into_iter()
+            .find(|token| token.network == self.network && token.is_native()))
+    }
+
+    //ETH amount, whether the native coin is registered or not
+    fn to_native_amount(&self, base_units: U256) -> Result<TokenAmount> {
+        let base_units = u128::try_from(base_units)
+            .map_err(|_| DomainError::Internal("ETH amount exceeds supported range".to_string()))?;
+        Ok(TokenAmount::from_base_units(base_units, NATIVE_DECIMALS))
+    }
+
+    //current base fee and the EIP-1559 fees ethers fills in when sending
+    async fn cost_estimate(&self, gas: U256, value: U256) -> Result<CostEstimate> {
+        let block = self
+            .provider
+            .get_block(BlockNumber::Latest)
+            .await
+            .map_err(upstream_error)?;
+        let base_fee = block
+            .and_then(|block| block.base_fee_per_gas)
+            .unwrap_or_default();
+        let (max_fee, max_priority_fee) = self
+            .provider
+            .estimate_eip1559_fees(None)
+            .await
+            .map_err(upstream_error)?;
+        Ok(CostEstimate {
+            gas,
+            base_fee,
+            max_priority_fee,
+            max_fee,
+            value,
+        })
+    }
+
+    //gas of a plain transfer, simulated from the sender
+    async fn estimate_transfer_gas(
+        &self,
+        sender: Address,
+        recipient: Address,
+        token: &W3Token,
+        amount: U256,
+    ) -> Result<U256> {
+        let gas = match token.is_native() {
+            true => {
+                let tx: TypedTransaction = Eip1559TransactionRequest::new()
+                    .from(sender)
+                    .to(recipient)
+                    .value(amount)
+                    .into();
+                self.provider
+                    .estimate_gas(&tx, None)
+                    .await
+                    .map_err(send_error)?
+            }
+            false => {
+                let contract =
+                    self.establish_contract_erc20(Arc::new(self.provider.clone()), token)?;
+                contract
+                    .method::<_, bool>("transfer", (recipient, amount))?
+                    .from(sender)
+                    .estimate_gas()
+                    .await
+                    .map_err(send_error)?
+            }
+        };
+        Ok(gas)
+    }
+
+    //ETH attached to the router call, the maximum input for an exact output
+    fn swap_value(&self, plan: &SwapPlan) -> U256 {
+        match (plan.method.pays_in_eth(), plan.mode) {
+            (false, _) => U256::zero(),
+            (true, SwapMode::ExactInput) => U256::from(plan.amount_in.base_units()),
+            (true, SwapMode::ExactOutput) => plan.amount_in_max,
+        }
+    }
+
+    //refuse before sending anything when the wallet cannot pay the worst case cost
+    async fn ensure_affordable(&self, payer: Address, estimate: &CostEstimate) -> Result<()> {
+        let balance = self
+            .provider
+            .get_balance(payer, None)
+            .await
+            .map_err(upstream_error)?;
+        if balance < estimate.max_cost() {
+            return Err(DomainError::InsufficientFunds(format!(
+                "Wallet {:?} holds {} ETH but the transaction needs up to {} ETH for gas and value",
+                payer,
+                self.to_native_amount(balance)?.format(),
+                self.to_native_amount(estimate.max_cost())?.format()
+            ))
+            .into());
+        }
+        Ok(())
+    }
+
+    async fn estimate_response(
+        &self,
+        operation: &str,
+        payer: Address,
+        estimate: &CostEstimate,
+    ) -> Result<CryptoEstimateResponseDTO> {
+        let eth_balance = self
+            .provider
+            .get_balance(payer, None)
+            .await
+            .map_err(upstream_error)?;
+
+        //the cost is valued like a portfolio holding of the native coin
+        let valuation = match self.native_token().await? {
+            Some(native) => {
+                let contract_multicall =
+                    self.establish_contract_multicall(Arc::new(self.provider.clone()))?;
+                self.valuate_balances(&contract_multicall, &[(native, estimate.expected_cost())])
+                    .await?
+            }
+            None => None,
+        };
+        let total_cost_usd = match valuation {
+            Some((valuation_token, values)) => match values.first().copied().flatten() {
+                Some(value) => Some(self.to_token_amount(&valuation_token, value)?.to_dto()),
+                None => None,
+            },
+            None => None,
+        };
+
+        let response_dto = CryptoEstimateResponseDTO {
+            operation: operation.to_string(),
+            address: format!("{:?}", payer),
+            gas_estimate: estimate.gas.to_string(),
+            base_fee_per_gas: estimate.base_fee.to_string(),
+            max_priority_fee_per_gas: estimate.max_priority_fee.to_string(),
+            max_fee_per_gas: estimate.max_fee.to_string(),
+            value: self.to_native_amount(estimate.value)?.to_dto(),
+            total_cost: self.to_native_amount(estimate.expected_cost())?.to_dto(),
+            max_total_cost: self.to_native_amount(estimate.max_cost())?.to_dto(),
+            total_cost_usd,
+            eth_balance: self.to_native_amount(eth_balance)?.to_dto(),
+            sufficient_funds: eth_balance >= estimate.max_cost(),
+            timestamp: Utc::now().timestamp().to_string(),
+        };
+        Ok(response_dto)
+    }
+
+    //run the transaction on the pending block before it is sent
+    //a revert is rejected with its decoded reason instead of being mined and paid for
+    async fn simulate<M: Middleware>(&self, client: &M, tx: &TypedTransaction) -> Result<()> {
+        client
+            .call(tx, Some(BlockNumber::Pending.into()))
+            .await
+            .map_err(revert_error)?;
+        Ok(())
+    }
+
+    //replay a failed transaction on the state before its block to read why it reverted
+    async fn replay_revert_reason(
+        &self,
+        transaction: &Transaction,
+        block_number: U64,
+    ) -> Option<String> {
+        let call: TypedTransaction = transaction.into();
+        let block = BlockId::from(block_number.saturating_sub(U64::one()));
+        match self.provider.call(&call, Some(block)).await {
+            std::result::Result::Ok(_) => None,
+            Err(error) => {
+                let response = RpcError::as_error_response(&error)?;
+                response
+                    .as_revert_data()
+                    .and_then(|data| decode_revert_reason(&data))
+                    .or_else(|| Some(response.message.clone()))
+            }
+        }
+    }
+
+    //send a transaction at the nonce of a pending one, so that only one of them can be mined
+    //a speed-up resends the same call, a cancel is a zero-value transfer to the wallet itself
+    async fn replace_transaction(
+        &self,
+        wallet_id: &str,
+        tx_hash: &str,
+        cancel: bool,
+    ) -> Result<CryptoReplacementResponseDTO> {
+        let hash = tx_hash
+            .parse::<H256>()
+            .map_err(|e| DomainError::Validation(format!("Invalid transaction hash: {}", e)))?;
+        let transaction = self
+            .provider
+            .get_transaction(hash)
+            .await
+            .map_err(upstream_error)?
+            .ok_or_else(|| DomainError::NotFound(format!("Transaction {:?} not found", hash)))?;
+        if transaction.block_number.is_some() {
+            return Err(DomainError::Validation(format!(
+                "Transaction {:?} is already mined",
+                hash
+            ))
+            .into());
+        }
+
+        let client = self.establish_signer_wallet(wallet_id).await?;
+        let address = client.address();
+        if transaction.from != address {
+            return Err(DomainError::Validation(format!(
+                "Transaction {:?} was not sent by wallet {}",
+                hash, wallet_id
+            ))
+            .into());
+        }
+
+        //legacy transactions only carry a gas price, it stands for both fees
+        //the replacement never pays less than what the network currently asks
+        let old_max_fee = transaction
+            .max_fee_per_gas
+            .or(transaction.gas_price)
+            .unwrap_or_default();
+        let old_priority_fee = transaction
+            .max_priority_fee_per_gas
+            .or(transaction.gas_price)
+            .unwrap_or_default();
+        let (current_max_fee, current_priority_fee) = self
+            .provider
+            .estimate_eip1559_fees(None)
+            .await
+            .map_err(upstream_error)?;
+        let (max_fee, max_priority_fee) = replacement_fees(
+            (old_max_fee, old_priority_fee),
+            (current_max_fee, current_priority_fee),
+        );
+
+        let replacement = Eip1559TransactionRequest::new()
+            .from(address)
+            .nonce(transaction.nonce)
+            .max_fee_per_gas(max_fee)
+            .max_priority_fee_per_gas(max_priority_fee);
+        let replacement = match (cancel, transaction.to) {
+            (true, _) => replacement
+                .to(address)
+                .value(U256::zero())
+                .gas(CANCEL_GAS_LIMIT),
+            (false, to) => {
+                let replacement = replacement
+                    .value(transaction.value)
+                    .data(transaction.input.clone())
+                    .gas(transaction.gas);
+                match to {
+                    Some(to) => replacement.to(to),
+                    None => replacement,
+                }
+            }
+        };
+
+        //the nonce is given, so the nonce store is left as it is
+        let replacement: TypedTransaction = replacement.into();
+        self.simulate(client.as_ref(), &replacement).await?;
+        let pending_tx = client
+            .send_transaction(replacement, None)
+            .await
+            .map_err(send_error)?;
+
+        let response_dto = CryptoReplacementResponseDTO {
+            transaction_hash: format!("{:?}", pending_tx.tx_hash()),
+            replaced_transaction_hash: format!("{:?}", hash),
+            address: format!("{:?}", address),
+            nonce: transaction.nonce.as_u64(),
+            max_fee_per_gas: max_fee.to_string(),
+            max_priority_fee_per_gas: max_priority_fee.to_string(),
+            timestamp: Utc::now().timestamp().to_string(),
+        };
+        Ok(response_dto)
+    }
+
+    //next nonce of an address signing with its own key, the shared nonces only cover custodial wallets
+    async fn pending_nonce(&self, address: Address) -> Result<U256> {
+        let nonce = self
+            .provider
+            .get_transaction_count(address, Some(BlockNumber::Pending.into()))
+            .await
+            .map_err(upstream_error)?;
+        Ok(nonce)
+    }
+
+    //fill a call in as an EIP-1559 transaction the client signs as is
+    fn unsigned_transaction(
+        &self,
+        chain_id: u64,
+        call: &TypedTransaction,
+        nonce: U256,
+        gas: U256,
+        estimate: &CostEstimate,
+    ) -> Result<CryptoUnsignedTransactionDTO> {
+        let from = call.from().copied().unwrap_or_default();
+        let to = call
+            .to_addr()
+            .copied()
+            .ok_or_else(|| DomainError::Internal("Transaction has no recipient".to_string()))?;
+        let value = call.value().copied().unwrap_or_default();
+        let data = call.data().cloned().unwrap_or_default();
+        let tx = TypedTransaction::Eip1559(
+            Eip1559TransactionRequest::new()
+                .from(from)
+                .to(to)
+                .value(value)
+                .data(data.clone())
+                .nonce(nonce)
+                .gas(gas)
+                .max_fee_per_gas(estimate.max_fee)
+                .max_priority_fee_per_gas(estimate.max_priority_fee)
+                .chain_id(chain_id),
+        );
+        Ok(CryptoUnsignedTransactionDTO {
+            chain_id,
+            from: format!("{:?}", from),
+            to: format!("{:?}", to),
+            nonce: nonce.as_u64(),
+            gas_limit: gas.to_string(),
+            max_fee_per_gas: estimate.max_fee.to_string(),
+            max_priority_fee_per_gas: estimate.max_priority_fee.to_string(),
+            value: value.to_string(),
+            data: data.to_string(),
+            unsigned_transaction: tx.rlp().to_string(),
+            signing_hash: format!("{:?}", tx.sighash()),
+        })
+    }
+
+    //a signed transaction is only broadcast when it is a call the build endpoints could have produced
+    async fn signed_operation(&self, tx: &TypedTransaction) -> Result<SignedOperation> {
+        let to = tx.to_addr().copied().ok_or_else(|| {
+            DomainError::Validation("Contract deployments cannot be broadcast".to_string())
+        })?;
+        let data = tx.data().cloned().unwrap_or_default();
+
+        //a plain ETH transfer carries no calldata
+        if data.is_empty() {
+            return Ok(SignedOperation::Transfer);
+        }
+        let selector: [u8; 4] = data
+            .get(..4)
+            .and_then(|selector| selector.try_into().ok())
+            .ok_or_else(|| DomainError::Validation("Malformed calldata".to_string()))?;
+        let arguments = &data[4..];
+
+        if to == self.router_address {
+            return router_operation(selector, arguments);
+        }
+
+        //any other call goes to a registered erc20 token of the network
+        let tokens = self.tokens.list_tokens().await?;
+        let registered = tokens.iter().any(|token| {
+            token.network == self.network
+                && !token.is_native()
+                && token.address.parse::<Address>().ok() == Some(to)
+        });
+        if !registered {
+            return Err(DomainError::Validation(format!(
+                "{:?} is neither the router nor a registered token",
+                to
+            ))
+            .into());
+        }
+        token_operation(selector, arguments, self.router_address)
+    }
+
+    fn token_address(&self, token: &W3Token) -> Result<Address> {
+        let address = token.address.parse::<Address>().map_err(|_| {
+            DomainError::Validation(format!("{} is not an ERC-20 token", token.symbol))
+        })?;
+        Ok(address)
+    }
+
+    //unix timestamp in seconds, the unit the router compares with block.timestamp
+    fn get_deadline(&self, future_seconds: u64) -> Result<U256> {
+        let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH)?;
+        let deadline = since_epoch
+            .as_secs()
+            .checked_add(future_seconds)
+            .ok_or_else(|| DomainError::Internal("Deadline overflow".to_string()))?;
+        Ok(U256::from(deadline))
+    }
+
+    //address of a custodial wallet, no key material is needed for this
+    async fn get_wallet_address(&self, wallet_id: &str) -> Result<Address> {
+        let stored_wallet = self.keystore.get_wallet(wallet_id).await?;
+        let address = stored_wallet
+            .w3_address
+            .parse::<Address>()
+            .map_err(|_| DomainError::Internal("Corrupted wallet address".to_string()))?;
+        Ok(address)
+    }
+}
+
+//errors coming back from the rpc node, only logged as transport errors print the rpc url and its api key
+//the router only takes its swap functions, with arguments it can decode
+fn router_operation(selector: [u8; 4], arguments: &[u8]) -> Result<SignedOperation> {
+    let abi: Abi = serde_json::from_str(CT_ROUTER02)?;
+    let swap_function = abi.functions().find(|function| {
+        function.name.starts_with("swap") && function.short_signature() == selector
+    });
+    match swap_function {
+        Some(function) if function.decode_input(arguments).is_ok() => Ok(SignedOperation::Swap),
+        _ => Err(DomainError::Validation(
+            "Only swap calls can be broadcast to the router".to_string(),
+        )
+        .into()),
+    }
+}
+
+//a registered token only takes transfers and approvals of the router
+fn token_operation(
+    selector: [u8; 4],
+    arguments: &[u8],
+    router_address: Address,
+) -> Result<SignedOperation> {
+    let abi: Abi = serde_json::from_str(CT_ERC20)?;
+    let transfer = abi.function("transfer")?;
+    let approve = abi.function("approve")?;
+    if selector == transfer.short_signature() {
+        transfer
+            .decode_input(arguments)
+            .map_err(|_| DomainError::Validation("Malformed transfer calldata".to_string()))?;
+        return Ok(SignedOperation::Transfer);
+    }
+    if selector == approve.short_signature() {
+        let spender = approve
+            .decode_input(arguments)
+            .map_err(|_| DomainError::Validation("Malformed approve calldata".to_string()))?
+            .first()
+            .cloned()
+            .and_then(|spender| spender.into_address());
+        //swaps are the only reason to approve, so the router is the only spender
+        return match spender == Some(router_address) {
+            true => Ok(SignedOperation::Approve),
+            false => Err(DomainError::Validation(
+                "Approvals can only be broadcast for the router".to_string(),
+            )
+            .into()),
+        };
+    }
+    Err(DomainError::Validation(
+        "Only transfer and approve calls can be broadcast to a token".to_string(),
+    )
+    .into())
+}
+
+fn upstream_error(error: impl Display) -> DomainError {
+    tracing::error!("upstream error : {}", error);
+    DomainError::Upstream(UPSTREAM_UNAVAILABLE.to_string())
+}
+
+//user supplied address, mixed-case input must carry a valid EIP-55 checksum
+//all lower or all upper case addresses have no checksum and are taken as they are
+fn parse_address(value: &str, field: &str) -> Result<Address> {
+    let address = value
+        .parse::<Address>()
+        .map_err(|e| DomainError::Validation(format!("Invalid {} address: {}", field, e)))?;
+    let hex = value.trim_start_matches("0x");
+    let is_mixed_case =
+        hex.chars().any(|c| c.is_ascii_lowercase()) && hex.chars().any(|c| c.is_ascii_uppercase());
+    if is_mixed_case && ethers::utils::to_checksum(&address, None) != format!("0x{}", hex) {
+        return Err(DomainError::Validation(format!(
+            "Invalid {} address: EIP-55 checksum mismatch",
+            field
+        ))
+        .into());
+    }
+    Ok(address)
+}
+
+//a * b / denominator on the full 512 bit product, none when the quotient does not fit a U256
+fn mul_div(a: U256, b: U256, denominator: U256) -> Option<U256> {
+    if denominator.is_zero() {
+        return None;
+    }
+    U256::try_from(a.full_mul(b) / U512::from(denominator)).ok()
+}
+
+//part of a pool amount owned by an LP balance, a balance above the supply is a broken pair
+fn pool_share(lp_balance: U256, amount: U256, total_supply: U256) -> Result<U256> {
+    let share = mul_div(lp_balance, amount, total_supply)
+        .ok_or_else(|| DomainError::Upstream("Invalid pair supply".to_string()))?;
+    Ok(share)
+}
+
+//fee of a replacement, a bit above the minimum bump so rounding never leaves it short
+fn bump_fee(fee: U256) -> U256 {
+    mul_div(
+        fee,
+        U256::from(10_000 + REPLACEMENT_FEE_BUMP_BPS),
+        U256::from(10_000),
+    )
+    .unwrap_or(U256::MAX)
+    .saturating_add(U256::one())
+}
+
+//max fee and priority fee of a replacement, both bumped and floored at the current network fees
+//the max fee is never below the priority fee, nodes reject such a transaction
+fn replacement_fees(old: (U256, U256), current: (U256, U256)) -> (U256, U256) {
+    let (old_max_fee, old_priority_fee) = old;
+    let (current_max_fee, current_priority_fee) = current;
+    let max_priority_fee = bump_fee(old_priority_fee).max(current_priority_fee);
+    let max_fee = bump_fee(old_max_fee)
+        .max(current_max_fee)
+        .max(max_priority_fee);
+    (max_fee, max_priority_fee)
+}
+
+//revert data carrying a Solidity Error(string), a Panic(uint256) or a custom error of the bundled ABIs
+fn decode_revert_reason(data: &[u8]) -> Option<String> {
+    if data.len() < 4 {
+        return None;
+    }
+    let (selector, payload) = data.split_at(4);
+    if selector == ethers::utils::id("Error(string)") {
+        let tokens = ethers::abi::decode(&[ethers::abi::ParamType::String], payload).ok()?;
+        return tokens.into_iter().next()?.into_string();
+    }
+    if selector == ethers::utils::id("Panic(uint256)") {
+        let tokens = ethers::abi::decode(&[ethers::abi::ParamType::Uint(256)], payload).ok()?;
+        let code = tokens.into_iter().next()?.into_uint()?;
+        return Some(format!("Panic(0x{:x}): {}", code, panic_reason(code)));
+    }
+    bundled_errors()
+        .iter()
+        .filter(|(error_selector, _)| error_selector[..] == *selector)
+        .find_map(|(_, error)| {
+            let tokens = error.decode(payload).ok()?;
+            let args = tokens
+                .iter()
+                .map(|token| token.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            Some(format!("{}({})", error.name, args))
+        })
+}
+
+//custom errors of the bundled ABIs with their selectors, parsed once per lambda instance
+fn bundled_errors() -> &'static [([u8; 4], ethers::abi::ethabi::AbiError)] {
+    static ERRORS: OnceLock<Vec<([u8; 4], ethers::abi::ethabi::AbiError)>> = OnceLock::new();
+    ERRORS.get_or_init(|| {
+        BUNDLED_ABIS
+            .iter()
+            .filter_map(|abi| serde_json::from_str::<Abi>(abi).ok())
+            .flat_map(|abi| abi.errors().cloned().collect::<Vec<_>>())
+            .map(|error| {
+                let mut selector = [0u8; 4];
+                selector.copy_from_slice(&error.signature()[..4]);
+                (selector, error)
+            })
+            .collect()
+    })
+}
+
+//meaning of the solidity panic codes
+fn panic_reason(code: U256) -> &'static str {
+    match code.low_u64() {
+        0x01 => "assertion failed",
+        0x11 => "arithmetic overflow or underflow",
+        0x12 => "division by zero",
+        0x21 => "invalid enum value",
+        0x22 => "invalid storage byte array",
+        0x31 => "pop on an empty array",
+        0x32 => "array index out of bounds",
+        0x41 => "out of memory",
+        0x51 => "call to an uninitialized function",
+        _ => "unknown panic",
+    }
+}
+
+//a call the node refused to run, reverts carry their decoded reason and anything else is an rpc failure
+fn revert_error(error: impl MiddlewareError) -> DomainError {
+    let response = match error.as_error_response() {
+        Some(response) => response,
+        None => return upstream_error(&error),
+    };
+    let reason = response
+        .as_revert_data()
+        .and_then(|data| decode_revert_reason(&data));
+    match reason {
+        Some(reason) => DomainError::Reverted(format!("Transaction would revert: {}", reason)),
+        None if response.message.to_lowercase().contains("revert") => {
+            DomainError::Reverted(format!("Transaction would revert: {}", response.message))
+        }
+        None => send_error(&response.message),
+    }
+}
+
+//the node answered but the call itself failed, as opposed to a transport failure
+fn is_execution_error<M: Middleware>(error: &ContractError<M>) -> bool {
+    error.is_revert()
+        || error
+            .as_middleware_error()
+            .and_then(|error| error.as_error_response())
+            .is_some()
+}
+
+//errors raised while broadcasting, the node reports an empty wallet as a plain message
+fn send_error(error: impl Display) -> DomainError {
+    let message = error.to_string();
+    if message.to_lowercase().contains("insufficient funds") {
+        DomainError::InsufficientFunds(message)
+    } else {
+        upstream_error(message)
+    }
+}
+
+#[async_trait]
+impl Web3Repository for InfuraRepository {
+    async fn transfer_token(
+        &self,
+        sender_wallet_id: &str,
+        receipient_address: &str,
+        amount: &str,
+        chain: &str,
+        relayed: bool,
+    ) -> Result<CryptoTransactionResponseDTO> {
+        let token = self.resolve_token(chain).await?;
+        let recipient = parse_address(receipient_address, "recipient")?;
+
+        let token_amount = self.parse_amount(&token, amount)?;
+        let decimal_amount = U256::from(token_amount.base_units());
+
+        if relayed {
+            let signer_address = self.get_wallet_address(sender_wallet_id).await?;
+            let (transaction_hash, relayer_address) = self
+                .relayed_transfer(sender_wallet_id, recipient, &token, &token_amount)
+                .await?;
+            let result = CryptoTransactionResponseDTO {
+                transaction_hash,
+                sender_address: format!("{:?}", signer_address),
+                receipient_address: receipient_address.to_string(),
+                amount: token_amount.to_dto(),
+                relayer_address: Some(format!("{:?}", relayer_address)),
+                timestamp: Utc::now().timestamp().to_string(),
+            };
+            return Ok(result);
+        }
+
+        let client = self.establish_signer_wallet(sender_wallet_id).await?;
+        let signer_address = format!("{:?}", client.address());
+
+        //check to know the token is native ETH or else
+        let transfer_tx: TypedTransaction = match token.is_native() {
+            //if it is ETH so no need to create contract erc20
+            true => TransactionRequest::new()
+                .from(client.address())
+                .to(recipient)
+                .value(decimal_amount)
+                .into(),
+            //if it is not ETH so must create erc20 contract
+            false => {
+                let contract = self.establish_contract_erc20(client.clone(), &token)?;
+                contract
+                    .method::<(Address, U256), H256>("transfer", (recipient, decimal_amount))?
+                    .tx
+            }
+        };
+
+        // Reject a transfer the token would revert, a paused token or a blacklisted recipient for example
+        self.simulate(client.as_ref(), &transfer_tx).await?;
+
+        // Reject the transfer before sending it when the wallet cannot pay the gas and the value
+        let gas = self
+            .estimate_transfer_gas(client.address(), recipient, &token, decimal_amount)
+            .await?;
+        let value = match token.is_native() {
+            true => decimal_amount,
+            false => U256::zero(),
+        };
+        let estimate = self.cost_estimate(gas, value).await?;
+        self.ensure_affordable(client.address(), &estimate).await?;
+
+        //the hash is returned without waiting, the status is followed with GET /crypto/transaction/{hash}
+        let pending_tx = client
+            .send_transaction(transfer_tx, None)
+            .await
+            .map_err(send_error)?;
+
+        let result = CryptoTransactionResponseDTO {
+            transaction_hash: format!("{:?}", pending_tx.tx_hash()),
+            sender_address: signer_address,
+            receipient_address: receipient_address.to_string(),
+            amount: token_amount.to_dto(),
+            relayer_address: None,
+            timestamp: Utc::now().timestamp().to_string(),
+        };
+        Ok(result)
+    }
+
+    async fn get_transaction_status(
+        &self,
+        tx_hash: &str,
+    ) -> Result<CryptoTransactionStatusResponseDTO> {
+        let hash = tx_hash
+            .parse::<H256>()
+            .map_err(|e| DomainError::Validation(format!("Invalid transaction hash: {}", e)))?;
+        let transaction = self
+            .provider
+            .get_transaction(hash)
+            .await
+            .map_err(upstream_error)?;
+        let receipt = self
+            .provider
+            .get_transaction_receipt(hash)
+            .await
+            .map_err(upstream_error)?;
+
+        let mut response_dto = CryptoTransactionStatusResponseDTO {
+            transaction_hash: format!("{:?}", hash),
+            status: TransactionStatus::Pending.map_status(),
+            block_number: None,
+            confirmations: 0,
+            gas_used: None,
+            effective_gas_price: None,
+            revert_reason: None,
+            timestamp: Utc::now().timestamp().to_string(),
+        };
+        match (transaction, receipt) {
+            //a node that knows neither the transaction nor a receipt has dropped it or never saw it
+            //the history record of the hash tells the two apart
+            (None, None) => response_dto.status = TransactionStatus::Dropped.map_status(),
+            (Some(_), None) => {}
+            (transaction, Some(receipt)) => {
+                let block_number = receipt.block_number.unwrap_or_default();
+                let latest_block = self
+                    .provider
+                    .get_block_number()
+                    .await
+                    .map_err(upstream_error)?;
+                let succeeded = receipt.status == Some(U64::from(1));
+                response_dto.status = match succeeded {
+                    true => TransactionStatus::Mined.map_status(),
+                    false => TransactionStatus::Failed.map_status(),
+                };
+                response_dto.block_number = Some(block_number.as_u64());
+                response_dto.confirmations =
+                    latest_block.as_u64().saturating_sub(block_number.as_u64()) + 1;
+                response_dto.gas_used = receipt.gas_used.map(|gas_used| gas_used.to_string());
+                response_dto.effective_gas_price = receipt
+                    .effective_gas_price
+                    .map(|gas_price| gas_price.to_string());
+                if let (false, Some(transaction)) = (succeeded, transaction) {
+                    response_dto.revert_reason =
+                        self.replay_revert_reason(&transaction, block_number).await;
+                }
+            }
+        }
+        Ok(response_dto)
+    }
+
+    async fn speed_up_transaction(
+        &self,
+        wallet_id: &str,
+        tx_hash: &str,
+    ) -> Result<CryptoReplacementResponseDTO> {
+        self.replace_transaction(wallet_id, tx_hash, false).await
+    }
+
+    async fn cancel_transaction(
+        &self,
+        wallet_id: &str,
+        tx_hash: &str,
+    ) -> Result<CryptoReplacementResponseDTO> {
+        self.replace_transaction(wallet_id, tx_hash, true).await
+    }
+
+    async fn get_balance(&self, address: &str, chain: &str) -> Result<TokenAmount> {
+        let address = parse_address(address, "wallet")?;
+        let token = self.resolve_token(chain).await?;
+        let client = Arc::new(self.provider.clone());
+
+        let balance = self.token_balance(client, &token, address).await?;
+        //convert the balance by the registered decimals
+        self.to_token_amount(&token, balance)
+    }
+
+    async fn get_portfolio(&self, address: &str) -> Result<CryptoPortfolioResponseDTO> {
+        let owner = parse_address(address, "wallet")?;
+        let client = Arc::new(self.provider.clone());
+        let contract_multicall = self.establish_contract_multicall(client)?;
+        let erc20_abi: Abi = serde_json::from_str(CT_ERC20)?;
+        let contract_erc20 = BaseContract::from(erc20_abi);
+
+        //the native balance is read through the multicall contract itself
+        let tokens = self.tokens.list_tokens().await?;
+        let mut holdings = Vec::<W3Token>::new();
+        let mut calls = Vec::<(Address, Bytes)>::new();
+        for token in tokens
+            .into_iter()
+            .filter(|token| token.network == self.network)
+        {
+            let call = match token.is_native() {
+                true => (
+                    self.multicall_address,
+                    contract_multicall.encode("getEthBalance", owner)?,
+                ),
+                false => (
+                    self.token_address(&token)?,
+                    contract_erc20.encode("balanceOf", owner)?,
+                ),
+            };
+            calls.push(call);
+            holdings.push(token);
+        }
+
+        let results = self.aggregate(&contract_multicall, calls).await?;
+        let mut balances = Vec::<(W3Token, U256)>::new();
+        for (token, result) in holdings.into_iter().zip(results) {
+            let method = match token.is_native() {
+                true => "getEthBalance",
+                false => "balanceOf",
+            };
+            let balance = result.and_then(|data| match token.is_native() {
+                true => contract_multicall
+                    .decode_output::<U256, _>(method, data)
+                    .ok(),
+                false => contract_erc20.decode_output::<U256, _>(method, data).ok(),
+            });
+            //a registered address without a token contract behind it is left out
+            match balance {
+                Some(balance) => balances.push((token, balance)),
+                None => tracing::warn!(
+                    "failed to read the balance of {} on {}",
+                    token.symbol,
+                    self.network
+                ),
+            }
+        }
+
+        let valuation = self
+            .valuate_balances(&contract_multicall, &balances)
+            .await?;
+        let mut holding_dtos = Vec::<CryptoHoldingDTO>::new();
+        let mut total_usd_value = U256::zero();
+        for (index, (token, balance)) in balances.iter().enumerate() {
+            let usd_value = match &valuation {
+                Some((valuation_token, values)) => match values[index] {
+                    Some(value) => {
+                        total_usd_value += value;
+                        Some(self.to_token_amount(valuation_token, value)?.to_dto())
+                    }
+                    None => None,
+                },
+                None => None,
+            };
+            let token_address = match token.is_native() {
+                true => String::new(),
+                false => token.address.clone(),
+            };
+            holding_dtos.push(CryptoHoldingDTO {
+                symbol: token.symbol.clone(),
+                token_address,
+                kind: token.kind.clone(),
+                balance: self.to_token_amount(token, *balance)?.to_dto(),
+                usd_value,
+            });
+        }
+        let total_usd_value = match &valuation {
+            Some((valuation_token, _)) => Some(
+                self.to_token_amount(valuation_token, total_usd_value)?
+                    .to_dto(),
+            ),
+            None => None,
+        };
+
+        let response_dto = CryptoPortfolioResponseDTO {
+            address: format!("{:?}", owner),
+            holdings: holding_dtos,
+            total_usd_value,
+            timestamp: Utc::now().timestamp().to_string(),
+        };
+        Ok(response_dto)
+    }
+
+    async fn get_wallet(&self, wallet_id: &str) -> Result<String> {
+        //just return the wallet address as string
+        let address = self.get_wallet_address(wallet_id).await?;
+        Ok(format!("{:?}", address))
+    }
+
+    async fn create_wallet(&self) -> Result<(String, String)> {
+        let wallet = LocalWallet::new(&mut thread_rng());
+        let address = format!("{:?}", wallet.address());
+
+        //only the encrypted key leaves this function
+        let w3_keystore = self
+            .cipher
+            .encrypt(&address, wallet.signer().to_bytes().as_slice())?;
+        let stored_wallet = W3Wallet {
+            wallet_id: Uuid::new_v4().to_string(),
+            w3_address: address.clone(),
+            w3_keystore,
+        };
+        self.keystore.insert_wallet(&stored_wallet).await?;
+
+        Ok((stored_wallet.wallet_id, address))
+    }
+
+    async fn swap(
+        &self,
+        from_token: &str,
+        to_token: &str,
+        amount: &str,
+        wallet_id: &str,
+        options: &SwapOptionsDTO,
+    ) -> Result<CryptoSwapResponseDTO> {
+        let settings = self.resolve_swap_settings(options)?;
+
+        // Establish client and signer
+        let client = self.establish_signer_wallet(wallet_id).await?;
+        let signer_address = client.address();
+        // The output goes to the signer unless another recipient was asked for
+        let recipient = settings.recipient.unwrap_or(signer_address);
+
+        // Get router contract
+        let contract_router = self.establish_contract_router(client.clone())?;
+        let router_address = contract_router.address();
+        let plan = self
+            .plan_swap(&contract_router, from_token, to_token, amount, &settings)
+            .await?;
+
+        // Set a deadline timestamp
+        let deadline = self.get_deadline(settings.deadline_seconds)?;
+
+        // Reject the swap before the approval is sent when the wallet cannot pay the gas and the ETH input
+        let gas = self
+            .estimate_swap_gas(&contract_router, &plan, Some(wallet_id), &settings)
+            .await?;
+        let estimate = self.cost_estimate(gas, self.swap_value(&plan)).await?;
+        self.ensure_affordable(signer_address, &estimate).await?;
+
+        // The swap can only be simulated once the router is approved, so the token input is checked here
+        if !plan.method.pays_in_eth() {
+            let balance = self
+                .token_balance(client.clone(), &plan.from_token, signer_address)
+                .await?;
+            if balance < plan.amount_in_max {
+                return Err(DomainError::InsufficientFunds(format!(
+                    "Wallet {:?} holds {} {} but the swap needs up to {}",
+                    signer_address,
+                    self.to_token_amount(&plan.from_token, balance)?.format(),
+                    plan.from_token.symbol,
+                    self.to_token_amount(&plan.from_token, plan.amount_in_max)?
+                        .format()
+                ))
+                .into());
+            }
+        }
+
+        // If swapping tokens , approve the router to spend your tokens.
+        // For an exact output only the maximum input is approved, native ETH is sent as msg.value instead
+        let approval = match plan.method.pays_in_eth() {
+            true => Approval::Covered,
+            false => {
+                self.ensure_allowance(
+                    client.clone(),
+                    &plan.from_token,
+                    router_address,
+                    plan.amount_in_max,
+                    settings.approve_max,
+                )
+                .await?
+            }
+        };
+
+        // Build the swap transaction.
+        // Right behind a dry-run approval the swap cannot be simulated, so it takes a fixed gas limit
+        let swap_tx = match approval.is_pending() {
+            true => self
+                .build_swap_call(&contract_router, &plan, recipient, deadline)?
+                .gas(UNAPPROVED_SWAP_GAS_LIMIT),
+            false => self.build_swap_call(&contract_router, &plan, recipient, deadline)?,
+        };
+
+        // Taxed tokens are measured by the balance delta of the recipient, only possible for an erc20 output
+        let balance_before = match plan.fee_on_transfer && !plan.method.pays_out_eth() {
+            true => Some(
+                self.token_balance(client.clone(), &plan.to_token, recipient)
+                    .await?,
+            ),
+            false => None,
+        };
+
+        // Exact-input swaps return their hash right away, GET /crypto/transaction/{hash} follows them.
+        // Exact-output and fee-on-transfer swaps wait for the amounts they really move, within RECEIPT_TIMEOUT_SECONDS.
+        // Behind a dry-run approval the allowance does not exist, so the swap cannot be simulated
+        if !approval.is_pending() {
+            self.simulate(client.as_ref(), &swap_tx.tx).await?;
+        }
+        let pending_swap_tx = swap_tx.send().await.map_err(send_error)?;
+        let tx_hash = format!("{:?}", pending_swap_tx.tx_hash());
+        // Nothing gets mined in dry run, and a swap not mined in time has no receipt yet.
+        // Both return the planned amounts instead
+        let wait_for_receipt =
+            (plan.mode == SwapMode::ExactOutput || plan.fee_on_transfer) && !self.dry_run;
+        let receipt = match wait_for_receipt {
+            true => self.await_receipt(pending_swap_tx, "Swap").await?,
+            false => None,
+        };
+        let (amount_in, amount_out) = match receipt {
+            None => (plan.amount_in, None),
+            Some(receipt) => {
+                if receipt.status != Some(U64::from(1)) {
+                    return Err(DomainError::Upstream(format!(
+                        "Swap transaction {} reverted",
+                        tx_hash
+                    ))
+                    .into());
+                }
+
+                let amount_in = match (plan.mode, self.spent_amount(&plan, &receipt)) {
+                    (SwapMode::ExactOutput, Some(spent)) => {
+                        self.to_token_amount(&plan.from_token, spent)?
+                    }
+                    _ => plan.amount_in,
+                };
+                let amount_out = match balance_before {
+                    Some(balance_before) => {
+                        let balance_after = self
+                            .token_balance(client.clone(), &plan.to_token, recipient)
+                            .await?;
+                        let received = balance_after.saturating_sub(balance_before);
+                        self.record_transfer_fee(&plan, received).await;
+                        Some(self.to_token_amount(&plan.to_token, received)?.to_dto())
+                    }
+                    None => None,
+                };
+                (amount_in, amount_out)
+            }
+        };
+        let response_dto = CryptoSwapResponseDTO {
+            transaction_hash: tx_hash,
+            approval_transaction_hash: approval.transaction_hash(),
+            address: format!("{:?}", signer_address),
+            mode: plan.mode.to_string(),
+            amount_in: amount_in.to_dto(),
+            amount_in_max: self.amount_in_max(&plan)?,
+            amount_out_min: self
+                .to_token_amount(&plan.to_token, plan.amount_out_min)?
+                .to_dto(),
+            amount_out,
+            from_token: from_token.to_string(),
+            to_token: to_token.to_string(),
+            path: plan.path_symbols(),
+            slippage_bps: plan.slippage_bps,
+            deadline: deadline.to_string(),
+            recipient: format!("{:?}", recipient),
+            timestamp: Utc::now().timestamp().to_string(),
+        };
+        Ok(response_dto)
+    }
+
+    async fn swap_quote(
+        &self,
+        from_token: &str,
+        to_token: &str,
+        amount: &str,
+        wallet_id: Option<&str>,
+        options: &SwapOptionsDTO,
+    ) -> Result<CryptoSwapQuoteResponseDTO> {
+        let settings = self.resolve_swap_settings(options)?;
+
+        //read only, the router is called through the plain provider
+        let client = Arc::new(self.provider.clone());
+        let contract_router = self.establish_contract_router(client.clone())?;
+        let plan = self
+            .plan_swap(&contract_router, from_token, to_token, amount, &settings)
+            .await?;
+
+        let price_impact_bps = self
+            .price_impact_bps(client, &contract_router, &plan)
+            .await?;
+        let gas_estimate = self
+            .estimate_swap_gas(&contract_router, &plan, wallet_id, &settings)
+            .await?;
+        let gas_price = self
+            .provider
+            .get_gas_price()
+            .await
+            .map_err(upstream_error)?;
+        let gas_cost = self.to_native_amount(gas_estimate * gas_price)?;
+
+        let response_dto = CryptoSwapQuoteResponseDTO {
+            from_token: from_token.to_string(),
+            to_token: to_token.to_string(),
+            path: plan.path_symbols(),
+            swap_method: plan.method.to_string(),
+            mode: plan.mode.to_string(),
+            amount_in: plan.amount_in.to_dto(),
+            amount_in_max: self.amount_in_max(&plan)?,
+            expected_amount_out: self
+                .to_token_amount(&plan.to_token, plan.expected_out)?
+                .to_dto(),
+            amount_out_min: self
+                .to_token_amount(&plan.to_token, plan.amount_out_min)?
+                .to_dto(),
+            slippage_bps: plan.slippage_bps,
+            price_impact_bps,
+            gas_estimate: gas_estimate.to_string(),
+            gas_cost: gas_cost.to_dto(),
+            timestamp: Utc::now().timestamp().to_string(),
+        };
+        Ok(response_dto)
+    }
+
+    async fn estimate_transfer(
+        &self,
+        sender_wallet_id: &str,
+        recipient_address: &str,
+        amount: &str,
+        chain: &str,
+        relayed: bool,
+    ) -> Result<CryptoEstimateResponseDTO> {
+        let token = self.resolve_token(chain).await?;
+        let recipient = parse_address(recipient_address, "recipient")?;
+        let token_amount = self.parse_amount(&token, amount)?;
+        let decimal_amount = U256::from(token_amount.base_units());
+
+        //the relayer pays the gas of relayed transfers, the sender only signs
+        if relayed {
+            self.ensure_relayed_minimum(&token, &token_amount)?;
+            let relayer = self.get_wallet_address(self.relayer_wallet_id()?).await?;
+            let estimate = self
+                .cost_estimate(U256::from(RELAYED_TRANSFER_GAS_FALLBACK), U256::zero())
+                .await?;
+            return self.estimate_response("transfer", relayer, &estimate).await;
+        }
+
+        let sender = self.get_wallet_address(sender_wallet_id).await?;
+        let gas = self
+            .estimate_transfer_gas(sender, recipient, &token, decimal_amount)
+            .await?;
+        let value = match token.is_native() {
+            true => decimal_amount,
+            false => U256::zero(),
+        };
+        let estimate = self.cost_estimate(gas, value).await?;
+        self.estimate_response("transfer", sender, &estimate).await
+    }
+
+    async fn estimate_swap(
+        &self,
+        from_token: &str,
+        to_token: &str,
+        amount: &str,
+        wallet_id: &str,
+        options: &SwapOptionsDTO,
+    ) -> Result<CryptoEstimateResponseDTO> {
+        let settings = self.resolve_swap_settings(options)?;
+        let sender = self.get_wallet_address(wallet_id).await?;
+
+        //read only, the router is called through the plain provider
+        let client = Arc::new(self.provider.clone());
+        let contract_router = self.establish_contract_router(client)?;
+        let plan = self
+            .plan_swap(&contract_router, from_token, to_token, amount, &settings)
+            .await?;
+        let gas = self
+            .estimate_swap_gas(&contract_router, &plan, Some(wallet_id), &settings)
+            .await?;
+        let estimate = self.cost_estimate(gas, self.swap_value(&plan)).await?;
+        self.estimate_response("swap", sender, &estimate).await
+    }
+
+    async fn build_transfer(
+        &self,
+        sender_address: &str,
+        recipient_address: &str,
+        amount: &str,
+        chain: &str,
+    ) -> Result<CryptoTransactionBuildResponseDTO> {
+        // The client signs for the chain id of the node, so the node has to serve the expected one
+        let chain_id = self.verify_chain_id().await?;
+        let token = self.resolve_token(chain).await?;
+        let sender = parse_address(sender_address, "sender")?;
+        let recipient = parse_address(recipient_address, "recipient")?;
+
+        let token_amount = self.parse_amount(&token, amount)?;
+        let decimal_amount = U256::from(token_amount.base_units());
+
+        let transfer_tx: TypedTransaction = match token.is_native() {
+            true => Eip1559TransactionRequest::new()
+                .from(sender)
+                .to(recipient)
+                .value(decimal_amount)
+                .into(),
+            false => {
+                let contract =
+                    self.establish_contract_erc20(Arc::new(self.provider.clone()), &token)?;
+                contract
+                    .method::<(Address, U256), H256>("transfer", (recipient, decimal_amount))?
+                    .from(sender)
+                    .tx
+            }
+        };
+
+        // Same checks as a custodial transfer, a client should not sign what would revert or cannot be paid
+        self.simulate(&self.provider, &transfer_tx).await?;
+        let gas = self
+            .estimate_transfer_gas(sender, recipient, &token, decimal_amount)
+            .await?;
+        let value = match token.is_native() {
+            true => decimal_amount,
+            false => U256::zero(),
+        };
+        let estimate = self.cost_estimate(gas, value).await?;
+        self.ensure_affordable(sender, &estimate).await?;
+
+        let nonce = self.pending_nonce(sender).await?;
+        let result = CryptoTransactionBuildResponseDTO {
+            transaction: self.unsigned_transaction(
+                chain_id,
+                &transfer_tx,
+                nonce,
+                gas,
+                &estimate,
+            )?,
+            sender_address: format!("{:?}", sender),
+            receipient_address: recipient_address.to_string(),
+            amount: token_amount.to_dto(),
+            timestamp: Utc::now().timestamp().to_string(),
+        };
+        Ok(result)
+    }
+
+    async fn build_swap(
+        &self,
+        from_token: &str,
+        to_token: &str,
+        amount: &str,
+        address: &str,
+        options: &SwapOptionsDTO,
+    ) -> Result<CryptoSwapBuildResponseDTO> {
+        // The client signs for the chain id of the node, so the node has to serve the expected one
+        let chain_id = self.verify_chain_id().await?;
+        let settings = self.resolve_swap_settings(options)?;
+        let sender = parse_address(address, "address")?;
+        let recipient = settings.recipient.unwrap_or(sender);
+
+        // Nothing is signed here, the router is called through the plain provider
+        let client = Arc::new(self.provider.clone());
+        let contract_router = self.establish_contract_router(client.clone())?;
+        let router_address = contract_router.address();
+        let plan = self
+            .plan_swap(&contract_router, from_token, to_token, amount, &settings)
+            .await?;
+        let deadline = self.get_deadline(settings.deadline_seconds)?;
+
+        // The swap can only be simulated once the router is approved, so the token input is checked here
+        if !plan.method.pays_in_eth() {
+            let balance = self
+                .token_balance(client.clone(), &plan.from_token, sender)
+                .await?;
+            if balance < plan.amount_in_max {
+                return Err(DomainError::InsufficientFunds(format!(
+                    "Wallet {:?} holds {} {} but the swap needs up to {}",
+                    sender,
+                    self.to_token_amount(&plan.from_token, balance)?.format(),
+                    plan.from_token.symbol,
+                    self.to_token_amount(&plan.from_token, plan.amount_in_max)?
+                        .format()
+                ))
+                .into());
+            }
+        }
+
+        // Permits need a second signature, so a missing allowance gets a plain approve to sign first
+        let allowance = match plan.method.pays_in_eth() {
+            true => U256::MAX,
+            false => {
+                self.get_allowance(client.clone(), &plan.from_token, sender, router_address)
+                    .await?
+            }
+        };
+        let approval = match allowance >= plan.amount_in_max {
+            true => None,
+            false => {
+                let approval_amount = match settings.approve_max {
+                    true => U256::MAX,
+                    false => plan.amount_in_max,
+                };
+                let token_contract =
+                    self.establish_contract_erc20(client.clone(), &plan.from_token)?;
+                let approve_tx = token_contract
+                    .method::<_, H256>("approve", (router_address, approval_amount))?
+                    .from(sender)
+                    .tx;
+                self.simulate(client.as_ref(), &approve_tx).await?;
+                let approve_gas = self
+                    .provider
+                    .estimate_gas(&approve_tx, None)
+                    .await
+                    .map_err(send_error)?;
+                Some((approve_tx, approve_gas))
+            }
+        };
+
+        // Behind its approval the swap can neither be simulated nor estimated, so it takes a fixed gas limit
+        let swap_tx = self
+            .build_swap_call(&contract_router, &plan, recipient, deadline)?
+            .from(sender)
+            .tx;
+        let swap_gas = match approval {
+            Some(_) => U256::from(UNAPPROVED_SWAP_GAS_LIMIT),
+            None => {
+                self.simulate(client.as_ref(), &swap_tx).await?;
+                self.provider
+                    .estimate_gas(&swap_tx, None)
+                    .await
+                    .map_err(send_error)?
+            }
+        };
+        let approve_gas = approval
+            .as_ref()
+            .map(|(_, approve_gas)| *approve_gas)
+            .unwrap_or_default();
+        let estimate = self
+            .cost_estimate(swap_gas + approve_gas, self.swap_value(&plan))
+            .await?;
+        self.ensure_affordable(sender, &estimate).await?;
+
+        // The approval takes the next nonce of the address and the swap the one after it
+        let nonce = self.pending_nonce(sender).await?;
+        let (approval_transaction, swap_nonce) = match approval {
+            Some((approve_tx, approve_gas)) => (
+                Some(self.unsigned_transaction(
+                    chain_id,
+                    &approve_tx,
+                    nonce,
+                    approve_gas,
+                    &estimate,
+                )?),
+                nonce + 1,
+            ),
+            None => (None, nonce),
+        };
+        let response_dto = CryptoSwapBuildResponseDTO {
+            approval_transaction,
+            transaction: self
+                .unsigned_transaction(chain_id, &swap_tx, swap_nonce, swap_gas, &estimate)?,
+            address: format!("{:?}", sender),
+            mode: plan.mode.to_string(),
+            amount_in: plan.amount_in.to_dto(),
+            amount_in_max: self.amount_in_max(&plan)?,
+            amount_out_min: self
+                .to_token_amount(&plan.to_token, plan.amount_out_min)?
+                .to_dto(),
+            from_token: from_token.to_string(),
+            to_token: to_token.to_string(),
+            path: plan.path_symbols(),
+            slippage_bps: plan.slippage_bps,
+            deadline: deadline.to_string(),
+            recipient: format!("{:?}", recipient),
+            timestamp: Utc::now().timestamp().to_string(),
+        };
+        Ok(response_dto)
+    }
+
+    async fn broadcast_transaction(
+        &self,
+        address: &str,
+        signed_transaction: &str,
+    ) -> Result<CryptoBroadcastResponseDTO> {
+        let chain_id = self.verify_chain_id().await?;
+        let sender = parse_address(address, "address")?;
+        let raw_tx = signed_transaction.parse::<Bytes>().map_err(|_| {
+            DomainError::Validation("signed_transaction must be hex encoded".to_string())
+        })?;
+
+        // Decoding recovers the signer from the signature into the from field
+        let (tx, _) = TypedTransaction::decode_signed(&ethers::utils::rlp::Rlp::new(&raw_tx))
+            .map_err(|e| DomainError::Validation(format!("Invalid signed transaction: {}", e)))?;
+        let request = match &tx {
+            TypedTransaction::Eip1559(request) => request,
+            _ => {
+                return Err(DomainError::Validation(
+                    "Only EIP-1559 transactions can be broadcast".to_string(),
+                )
+                .into())
+            }
+        };
+        if request.chain_id != Some(U64::from(chain_id)) {
+            return Err(DomainError::Validation(format!(
+                "Transaction is signed for chain {} but the service runs on chain {}",
+                request.chain_id.unwrap_or_default(),
+                chain_id
+            ))
+            .into());
+        }
+        if request.from != Some(sender) {
+            return Err(DomainError::Validation(format!(
+                "Transaction is signed by {:?} instead of {:?}",
+                request.from.unwrap_or_default(),
+                sender
+            ))
+            .into());
+        }
+        //the nonce is set by the client, it is not bounded like the ones the node hands out
+        let nonce = u64::try_from(request.nonce.unwrap_or_default()).map_err(|_| {
+            DomainError::Validation("Transaction nonce exceeds the u64 range".to_string())
+        })?;
+        let operation = self.signed_operation(&tx).await?;
+
+        // A transaction queued behind other pending ones of the sender may depend on them,
+        // a swap behind its approval for example, so only the next one to be mined is simulated
+        let mined_nonce = self
+            .provider
+            .get_transaction_count(sender, None)
+            .await
+            .map_err(upstream_error)?;
+        if U256::from(nonce) == mined_nonce {
+            self.simulate(&self.provider, &tx).await?;
+        }
+
+        // The hash of a typed transaction is the hash of its signed envelope, known without sending it
+        let tx_hash = match self.dry_run {
+            true => H256::from(ethers::utils::keccak256(&raw_tx)),
+            false => {
+                let pending_tx = self
+                    .provider
+                    .send_raw_transaction(raw_tx)
+                    .await
+                    .map_err(send_error)?;
+                pending_tx.tx_hash()
+            }
+        };
+        let response_dto = CryptoBroadcastResponseDTO {
+            transaction_hash: format!("{:?}", tx_hash),
+            address: format!("{:?}", sender),
+            operation: operation.to_string(),
+            to: format!("{:?}", tx.to_addr().copied().unwrap_or_default()),
+            nonce,
+            timestamp: Utc::now().timestamp().to_string(),
+        };
+        Ok(response_dto)
+    }
+
+    async fn wrap(&self, wallet_id: &str, amount: &str) -> Result<CryptoWrapResponseDTO> {
+        self.convert_native(wallet_id, amount, true).await
+    }
+
+    async fn unwrap(&self, wallet_id: &str, amount: &str) -> Result<CryptoWrapResponseDTO> {
+        self.convert_native(wallet_id, amount, false).await
+    }
+
+    async fn get_allowances(
+        &self,
+        wallet_id: &str,
+        spender: Option<&str>,
+    ) -> Result<Vec<CryptoAllowanceResponseDTO>> {
+        let owner = self.get_wallet_address(wallet_id).await?;
+        let spender = self.resolve_spender(spender)?;
+        let client = Arc::new(self.provider.clone());
+
+        let tokens = self.tokens.list_tokens().await?;
+        let mut allowances = Vec::<CryptoAllowanceResponseDTO>::new();
+        for token in tokens
+            .iter()
+            .filter(|token| token.network == self.network && !token.is_native())
+        {
+            let allowance = self
+                .get_allowance(client.clone(), token, owner, spender)
+                .await?;
+            //approvals of U256::MAX do not fit an amount, they are reported as unlimited
+            let allowance = match u128::try_from(allowance) {
+                std::result::Result::Ok(base_units) => {
+                    Some(token.amount_from_base_units(base_units).to_dto())
+                }
+                Err(_) => None,
+            };
+            allowances.push(CryptoAllowanceResponseDTO {
+                token: token.symbol.clone(),
+                token_address: token.address.clone(),
+                spender: format!("{:?}", spender),
+                unlimited: allowance.is_none(),
+                allowance,
+            });
+        }
+        Ok(allowances)
+    }
+
+    async fn revoke_allowance(
+        &self,
+        wallet_id: &str,
+        token: &str,
+        spender: Option<&str>,
+    ) -> Result<CryptoAllowanceRevokeResponseDTO> {
+        let spender = self.resolve_spender(spender)?;
+        let token = self.resolve_token(token).await?;
+        if token.is_native() {
+            return Err(DomainError::Validation(format!(
+                "{} has no allowance to revoke",
+                token.symbol
+            ))
+            .into());
+        }
+        let client = self.establish_signer_wallet(wallet_id).await?;
+        let signer_address = client.address();
+
+        let token_contract = self.establish_contract_erc20(client.clone(), &token)?;
+        let revoke_tx = token_contract.method::<_, H256>("approve", (spender, U256::zero()))?;
+        self.simulate(client.as_ref(), &revoke_tx.tx).await?;
+        let pending_tx = revoke_tx.send().await.map_err(send_error)?;
+
+        let response_dto = CryptoAllowanceRevokeResponseDTO {
+            transaction_hash: format!("{:?}", pending_tx.tx_hash()),
+            address: format!("{:?}", signer_address),
+            token: token.symbol,
+            spender: format!("{:?}", spender),
+            timestamp: Utc::now().timestamp().to_string(),
+        };
+        Ok(response_dto)
+    }
+
+    async fn add_liquidity(
+        &self,
+        wallet_id: &str,
+        token_a: &str,
+        token_b: &str,
+        amount_a: &str,
+        amount_b: &str,
+        options: &LiquidityOptionsDTO,
+    ) -> Result<CryptoLiquidityResponseDTO> {
+        let settings = self.resolve_liquidity_settings(options)?;
+        let ((detect_a, a_native), (detect_b, b_native)) =
+            self.resolve_liquidity_tokens(token_a, token_b).await?;
+        let desired_a = U256::from(self.parse_amount(&detect_a, amount_a)?.base_units());
+        let desired_b = U256::from(self.parse_amount(&detect_b, amount_b)?.base_units());
+        let address_a = self.token_address(&detect_a)?;
+        let address_b = self.token_address(&detect_b)?;
+
+        let client = self.establish_signer_wallet(wallet_id).await?;
+        let signer_address = client.address();
+        let contract_router = self.establish_contract_router(client.clone())?;
+        let router_address = contract_router.address();
+        let pair = self
+            .pair_state(client.clone(), &contract_router, address_a, address_b)
+            .await?;
+
+        // Same computation as the router: one side is deposited in full, the other follows the pool price.
+        // A new or empty pool takes both amounts as they are, they set its price
+        let (used_a, used_b) = match &pair {
+            Some(pair) if !pair.reserve_a.is_zero() && !pair.reserve_b.is_zero() => {
+                let optimal_b: U256 = contract_router
+                    .method("quote", (desired_a, pair.reserve_a, pair.reserve_b))?
+                    .call()
+                    .await
+                    .map_err(upstream_error)?;
+                if optimal_b <= desired_b {
+                    (desired_a, optimal_b)
+                } else {
+                    let optimal_a: U256 = contract_router
+                        .method("quote", (desired_b, pair.reserve_b, pair.reserve_a))?
+                        .call()
+                        .await
+                        .map_err(upstream_error)?;
+                    (optimal_a, desired_b)
+                }
+            }
+            _ => (desired_a, desired_b),
+        };
+        let slippage_bps = settings.slippage_bps;
+        let amount_a_min = used_a * U256::from(10_000 - slippage_bps) / U256::from(10_000);
+        let amount_b_min = used_b * U256::from(10_000 - slippage_bps) / U256::from(10_000);
+
+        let deadline = self.get_deadline(settings.deadline_seconds)?;
+
+        // Approve the router on each erc20 side, native ETH is sent as msg.value.
+        // The deposit cannot be simulated behind a dry-run approval
+        let mut approval_hashes = Vec::<String>::new();
+        let mut approval_pending = false;
+        for (token, is_native, desired) in [
+            (&detect_a, a_native, desired_a),
+            (&detect_b, b_native, desired_b),
+        ] {
+            if is_native {
+                continue;
+            }
+            let approval = self
+                .ensure_allowance(
+                    client.clone(),
+                    token,
+                    router_address,
+                    desired,
+                    settings.approve_max,
+                )
+                .await?;
+            if let Some(transaction_hash) = approval.transaction_hash() {
+                approval_hashes.push(transaction_hash);
+            }
+            approval_pending |= approval.is_pending();
+        }
+
+        let liquidity_tx = match (a_native, b_native) {
+            (true, _) => contract_router
+                .method::<_, H256>(
+                    "addLiquidityETH",
+                    (
+                        address_b,
+                        desired_b,
+                        amount_b_min,
+                        amount_a_min,
+                        signer_address,
+                        deadline,
+                    ),
+                )?
+                .value(desired_a),
+            (_, true) => contract_router
+                .method::<_, H256>(
+                    "addLiquidityETH",
+                    (
+                        address_a,
+                        desired_a,
+                        amount_a_min,
+                        amount_b_min,
+                        signer_address,
+                        deadline,
+                    ),
+                )?
+                .value(desired_b),
+            _ => contract_router.method::<_, H256>(
+                "addLiquidity",
+                (
+                    address_a,
+                    address_b,
+                    desired_a,
+                    desired_b,
+                    amount_a_min,
+                    amount_b_min,
+                    signer_address,
+                    deadline,
+                ),
+            )?,
+        };
+        let liquidity_tx = match (approval_pending, &pair) {
+            (true, Some(_)) => liquidity_tx.gas(UNAPPROVED_LIQUIDITY_GAS_LIMIT),
+            (true, None) => liquidity_tx.gas(UNAPPROVED_NEW_PAIR_GAS_LIMIT),
+            (false, _) => liquidity_tx,
+        };
+
+        if !approval_pending {
+            self.simulate(client.as_ref(), &liquidity_tx.tx).await?;
+        }
+        let pending_tx = liquidity_tx.send().await.map_err(send_error)?;
+        let tx_hash = format!("{:?}", pending_tx.tx_hash());
+        let receipt = self.confirm_liquidity_receipt(pending_tx, &tx_hash).await?;
+
+        // The pair is deployed by the deposit itself when the pool did not exist.
+        // Until the deposit is mined, and never in dry run, a new pool has neither an address nor an LP balance
+        let pair_address = match &pair {
+            Some(pair) => Some(pair.address),
+            None if receipt.is_none() => None,
+            None => Some(
+                self.get_pair_address(client.clone(), &contract_router, address_a, address_b)
+                    .await?,
+            ),
+        };
+        let (deposited_a, deposited_b) = pair_address
+            .zip(receipt.as_ref())
+            .and_then(|(pair_address, receipt)| {
+                self.pair_event_amounts(
+                    receipt,
+                    pair_address,
+                    "Mint(address,uint256,uint256)",
+                    address_a < address_b,
+                )
+            })
+            .unwrap_or((used_a, used_b));
+        let minted = pair_address
+            .zip(receipt.as_ref())
+            .and_then(|(pair_address, receipt)| {
+                self.minted_liquidity(receipt, pair_address, signer_address)
+            })
+            .unwrap_or_default();
+        let lp_balance: U256 = match pair_address {
+            Some(pair_address) => self
+                .establish_contract_pair(client, pair_address)?
+                .method("balanceOf", signer_address)?
+                .call()
+                .await
+                .map_err(upstream_error)?,
+            None => U256::zero(),
+        };
+
+        let response_dto = CryptoLiquidityResponseDTO {
+            transaction_hash: tx_hash,
+            approval_transaction_hashes: approval_hashes,
+            address: format!("{:?}", signer_address),
+            pair_address: format!("{:?}", pair_address.unwrap_or_default()),
+            token_a: token_a.to_string(),
+            token_b: token_b.to_string(),
+            amount_a: self.to_token_amount(&detect_a, deposited_a)?.to_dto(),
+            amount_b: self.to_token_amount(&detect_b, deposited_b)?.to_dto(),
+            amount_a_min: self.to_token_amount(&detect_a, amount_a_min)?.to_dto(),
+            amount_b_min: self.to_token_amount(&detect_b, amount_b_min)?.to_dto(),
+            liquidity: self.to_lp_amount(minted)?.to_dto(),
+            lp_balance: self.to_lp_amount(lp_balance)?.to_dto(),
+            slippage_bps,
+            deadline: deadline.to_string(),
+            timestamp: Utc::now().timestamp().to_string(),
+        };
+        Ok(response_dto)
+    }
+
+    async fn remove_liquidity(
+        &self,
+        wallet_id: &str,
+        token_a: &str,
+        token_b: &str,
+        liquidity: &str,
+        options: &LiquidityOptionsDTO,
+    ) -> Result<CryptoLiquidityResponseDTO> {
+        let settings = self.resolve_liquidity_settings(options)?;
+        let ((detect_a, a_native), (detect_b, b_native)) =
+            self.resolve_liquidity_tokens(token_a, token_b).await?;
+        let address_a = self.token_address(&detect_a)?;
+        let address_b = self.token_address(&detect_b)?;
+        let liquidity = U256::from(TokenAmount::parse(liquidity, LP_DECIMALS)?.base_units());
+
+        let client = self.establish_signer_wallet(wallet_id).await?;
+        let signer_address = client.address();
+        let contract_router = self.establish_contract_router(client.clone())?;
+        let router_address = contract_router.address();
+        let pair = self
+            .pair_state(client.clone(), &contract_router, address_a, address_b)
+            .await?
+            .filter(|pair| !pair.total_supply.is_zero())
+            .ok_or_else(|| {
+                DomainError::Validation(format!(
+                    "No liquidity pool for {}/{}",
+                    detect_a.symbol, detect_b.symbol
+                ))
+            })?;
+
+        let contract_pair = self.establish_contract_pair(client.clone(), pair.address)?;
+        let lp_balance: U256 = contract_pair
+            .method("balanceOf", signer_address)?
+            .call()
+            .await
+            .map_err(upstream_error)?;
+        if lp_balance < liquidity {
+            return Err(DomainError::InsufficientFunds(format!(
+                "LP balance {} is lower than {}",
+                self.to_lp_amount(lp_balance)?.format(),
+                self.to_lp_amount(liquidity)?.format()
+            ))
+            .into());
+        }
+
+        // The pair pays out its reserves in proportion of the burned LP tokens
+        let slippage_bps = settings.slippage_bps;
+        let expected_a = pool_share(liquidity, pair.reserve_a, pair.total_supply)?;
+        let expected_b = pool_share(liquidity, pair.reserve_b, pair.total_supply)?;
+        let amount_a_min = expected_a * U256::from(10_000 - slippage_bps) / U256::from(10_000);
+        let amount_b_min = expected_b * U256::from(10_000 - slippage_bps) / U256::from(10_000);
+
+        let deadline = self.get_deadline(settings.deadline_seconds)?;
+
+        // LP tokens support EIP-2612, the router takes the permit within the removal itself.
+        // Only an existing allowance skips it, and a pair refusing the permit falls back to approve
+        let allowance: U256 = contract_pair
+            .method("allowance", (signer_address, router_address))?
+            .call()
+            .await
+            .map_err(upstream_error)?;
+        let mut approval_hashes = Vec::<String>::new();
+        let mut approval_pending = false;
+        let permit = match allowance >= liquidity {
+            true => None,
+            false => {
+                let value = match settings.approve_max {
+                    true => U256::MAX,
+                    false => liquidity,
+                };
+                let signature = self
+                    .sign_permit(
+                        client.clone(),
+                        pair.address,
+                        router_address,
+                        value,
+                        deadline,
+                    )
+                    .await?;
+                if signature.is_none() {
+                    let approve_tx =
+                        contract_pair.method::<_, H256>("approve", (router_address, value))?;
+                    self.simulate(client.as_ref(), &approve_tx.tx).await?;
+                    let pending_approve_tx = approve_tx.send().await.map_err(send_error)?;
+                    let approval_hash = format!("{:?}", pending_approve_tx.tx_hash());
+                    match self.dry_run {
+                        true => approval_pending = true,
+                        false => {
+                            self.confirm_approval(pending_approve_tx, &approval_hash)
+                                .await?
+                        }
+                    }
+                    approval_hashes.push(approval_hash);
+                }
+                signature
+            }
+        };
+
+        // Pools with native ETH pay it out unwrapped, taxed tokens need the supporting variants
+        let eth_side = match (a_native, b_native) {
+            (true, _) => Some((&detect_b, address_b, amount_b_min, amount_a_min)),
+            (_, true) => Some((&detect_a, address_a, amount_a_min, amount_b_min)),
+            _ => None,
+        };
+        let liquidity_tx = match (eth_side, &permit) {
+            (Some((token, token_address, token_min, eth_min)), None) => {
+                let method = match token.fee_on_transfer {
+                    true => "removeLiquidityETHSupportingFeeOnTransferTokens",
+                    false => "removeLiquidityETH",
+                };
+                contract_router.method::<_, H256>(
+                    method,
+                    (
+                        token_address,
+                        liquidity,
+                        token_min,
+                        eth_min,
+                        signer_address,
+                        deadline,
+                    ),
+                )?
+            }
+            (Some((token, token_address, token_min, eth_min)), Some(signature)) => {
+                let method = match token.fee_on_transfer {
+                    true => "removeLiquidityETHWithPermitSupportingFeeOnTransferTokens",
+                    false => "removeLiquidityETHWithPermit",
+                };
+                contract_router.method::<_, H256>(
+                    method,
+                    (
+                        token_address,
+                        liquidity,
+                        token_min,
+                        eth_min,
+                        signer_address,
+                        deadline,
+                        settings.approve_max,
+                        signature.v,
+                        signature.r,
+                        signature.s,
+                    ),
+                )?
+            }
+            (None, None) => contract_router.method::<_, H256>(
+                "removeLiquidity",
+                (
+                    address_a,
+                    address_b,
+                    liquidity,
+                    amount_a_min,
+                    amount_b_min,
+                    signer_address,
+                    deadline,
+                ),
+            )?,
+            (None, Some(signature)) => contract_router.method::<_, H256>(
+                "removeLiquidityWithPermit",
+                (
+                    address_a,
+                    address_b,
+                    liquidity,
+                    amount_a_min,
+                    amount_b_min,
+                    signer_address,
+                    deadline,
+                    settings.approve_max,
+                    signature.v,
+                    signature.r,
+                    signature.s,
+                ),
+            )?,
+        };
+
+        // A dry-run approve never reaches the chain, the removal can neither be simulated nor estimated
+        let liquidity_tx = match approval_pending {
+            true => liquidity_tx.gas(UNAPPROVED_LIQUIDITY_GAS_LIMIT),
+            false => {
+                self.simulate(client.as_ref(), &liquidity_tx.tx).await?;
+                liquidity_tx
+            }
+        };
+        let pending_tx = liquidity_tx.send().await.map_err(send_error)?;
+        let tx_hash = format!("{:?}", pending_tx.tx_hash());
+        let receipt = self.confirm_liquidity_receipt(pending_tx, &tx_hash).await?;
+
+        let (withdrawn_a, withdrawn_b) = receipt
+            .as_ref()
+            .and_then(|receipt| {
+                self.pair_event_amounts(
+                    receipt,
+                    pair.address,
+                    "Burn(address,uint256,uint256,address)",
+                    address_a < address_b,
+                )
+            })
+            .unwrap_or((expected_a, expected_b));
+        let lp_balance: U256 = contract_pair
+            .method("balanceOf", signer_address)?
+            .call()
+            .await
+            .map_err(upstream_error)?;
+
+        let response_dto = CryptoLiquidityResponseDTO {
+            transaction_hash: tx_hash,
+            approval_transaction_hashes: approval_hashes,
+            address: format!("{:?}", signer_address),
+            pair_address: format!("{:?}", pair.address),
+            token_a: token_a.to_string(),
+            token_b: token_b.to_string(),
+            amount_a: self.to_token_amount(&detect_a, withdrawn_a)?.to_dto(),
+            amount_b: self.to_token_amount(&detect_b, withdrawn_b)?.to_dto(),
+            amount_a_min: self.to_token_amount(&detect_a, amount_a_min)?.to_dto(),
+            amount_b_min: self.to_token_amount(&detect_b, amount_b_min)?.to_dto(),
+            liquidity: self.to_lp_amount(liquidity)?.to_dto(),
+            lp_balance: self.to_lp_amount(lp_balance)?.to_dto(),
+            slippage_bps,
+            deadline: deadline.to_string(),
+            timestamp: Utc::now().timestamp().to_string(),
+        };
+        Ok(response_dto)
+    }
+
+    async fn get_liquidity_position(
+        &self,
+        wallet_id: &str,
+        token_a: &str,
+        token_b: &str,
+    ) -> Result<CryptoLiquidityPositionResponseDTO> {
+        let owner = self.get_wallet_address(wallet_id).await?;
+        let ((detect_a, _), (detect_b, _)) =
+            self.resolve_liquidity_tokens(token_a, token_b).await?;
+        let address_a = self.token_address(&detect_a)?;
+        let address_b = self.token_address(&detect_b)?;
+
+        //read only, the router and the pair are called through the plain provider
+        let client = Arc::new(self.provider.clone());
+        let contract_router = self.establish_contract_router(client.clone())?;
+        let pair = self
+            .pair_state(client.clone(), &contract_router, address_a, address_b)
+            .await?
+            .ok_or_else(|| {
+                DomainError::Validation(format!(
+                    "No liquidity pool for {}/{}",
+                    detect_a.symbol, detect_b.symbol
+                ))
+            })?;
+
+        let contract_pair = self.establish_contract_pair(client, pair.address)?;
+        let lp_balance: U256 = contract_pair
+            .method("balanceOf", owner)?
+            .call()
+            .await
+            .map_err(upstream_error)?;
+        let (share_bps, amount_a, amount_b) = match pair.total_supply.is_zero() {
+            true => (U256::zero(), U256::zero(), U256::zero()),
+            false => (
+                pool_share(lp_balance, U256::from(10_000), pair.total_supply)?,
+                pool_share(lp_balance, pair.reserve_a, pair.total_supply)?,
+                pool_share(lp_balance, pair.reserve_b, pair.total_supply)?,
+            ),
+        };
+
+        let response_dto = CryptoLiquidityPositionResponseDTO {
+            address: format!("{:?}", owner),
+            pair_address: format!("{:?}", pair.address),
+            token_a: token_a.to_string(),
+            token_b: token_b.to_string(),
+            lp_balance: self.to_lp_amount(lp_balance)?.to_dto(),
+            total_supply: self.to_lp_amount(pair.total_supply)?.to_dto(),
+            share_bps: share_bps.as_u32(),
+            amount_a: self.to_token_amount(&detect_a, amount_a)?.to_dto(),
+            amount_b: self.to_token_amount(&detect_b, amount_b)?.to_dto(),
+        };
+        Ok(response_dto)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mul_div_keeps_the_full_product() {
+        let half = U256::MAX / 2;
+        assert_eq!(mul_div(half, U256::from(4), U256::from(8)), Some(half / 2));
+        assert_eq!(
+            mul_div(U256::from(7), U256::from(3), U256::from(2)),
+            Some(U256::from(10))
+        );
+    }
+
+    #[test]
+    fn mul_div_rejects_overflow_and_zero_denominator() {
+        assert_eq!(mul_div(U256::MAX, U256::from(2), U256::one()), None);
+        assert_eq!(mul_div(U256::one(), U256::one(), U256::zero()), None);
+    }
+
+    #[test]
+    fn pool_share_is_proportional_to_the_supply() {
+        let share = pool_share(U256::from(25), U256::from(1_000), U256::from(100)).unwrap();
+        assert_eq!(share, U256::from(250));
+        assert!(pool_share(U256::one(), U256::one(), U256::zero()).is_err());
+    }
+
+    //the EIP-55 example address
+    const CHECKSUMMED: &str = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+
+    fn is_validation(result: Result<Address>) -> bool {
+        matches!(
+            result.map_err(|error| DomainError::from_anyhow(&error)),
+            Err(DomainError::Validation(_))
+        )
+    }
+
+    #[test]
+    fn parse_address_takes_single_case_addresses_as_they_are() {
+        let expected = CHECKSUMMED.parse::<Address>().unwrap();
+        let lower = CHECKSUMMED.to_lowercase();
+        let upper = format!("0x{}", CHECKSUMMED[2..].to_uppercase());
+        assert_eq!(parse_address(&lower, "recipient").unwrap(), expected);
+        assert_eq!(parse_address(&upper, "recipient").unwrap(), expected);
+    }
+
+    #[test]
+    fn parse_address_accepts_a_valid_checksum() {
+        let expected = CHECKSUMMED.parse::<Address>().unwrap();
+        assert_eq!(parse_address(CHECKSUMMED, "recipient").unwrap(), expected);
+    }
+
+    #[test]
+    fn parse_address_rejects_a_wrong_checksum() {
+        let wrong = CHECKSUMMED.replace("aAeb", "AAeb");
+        assert!(is_validation(parse_address(&wrong, "recipient")));
+    }
+
+    #[test]
+    fn parse_address_rejects_wrong_length_and_non_hex() {
+        assert!(is_validation(parse_address(
+            "0x5aaeb6053f3e94c9b9a09f33669435e7ef1bea",
+            "recipient"
+        )));
+        assert!(is_validation(parse_address(
+            &format!("{}00", CHECKSUMMED.to_lowercase()),
+            "recipient"
+        )));
+        assert!(is_validation(parse_address(
+            "0xzzaeb6053f3e94c9b9a09f33669435e7ef1beaed",
+            "recipient"
+        )));
+        assert!(is_validation(parse_address("", "recipient")));
+    }
+
+    //geth and most nodes refuse a replacement paying less than old * 110 / 100
+    fn meets_node_minimum(old: U256, new: U256) -> bool {
+        new * U256::from(100) >= old * U256::from(110) && new > old
+    }
+
+    #[test]
+    fn bump_fee_meets_the_replacement_minimum_for_small_fees() {
+        //integer division rounds these down, the +1 keeps them above the minimum
+        for old in 0..1_000u64 {
+            let old = U256::from(old);
+            assert!(meets_node_minimum(old, bump_fee(old)), "fee {}", old);
+        }
+    }
+
+    #[test]
+    fn bump_fee_raises_by_twelve_and_a_half_percent() {
+        let gwei = U256::exp10(9);
+        assert_eq!(bump_fee(gwei * 8), gwei * 9 + 1);
+        assert!(meets_node_minimum(gwei * 8, bump_fee(gwei * 8)));
+        assert_eq!(bump_fee(U256::MAX), U256::MAX);
+    }
+
+    #[test]
+    fn replacement_fees_never_go_below_the_network() {
+        let gwei = U256::exp10(9);
+        //a stuck transaction far below the current fees is raised to them
+        let (max_fee, priority_fee) = replacement_fees((gwei, gwei / 10), (gwei * 30, gwei * 2));
+        assert_eq!((max_fee, priority_fee), (gwei * 30, gwei * 2));
+        //above the network fees, the bump decides
+        let (max_fee, priority_fee) =
+            replacement_fees((gwei * 40, gwei * 4), (gwei * 30, gwei * 2));
+        assert_eq!((max_fee, priority_fee), (gwei * 45 + 1, gwei * 9 / 2 + 1));
+        //the max fee covers a bumped priority fee above it
+        let (max_fee, priority_fee) = replacement_fees((gwei, gwei * 2), (gwei, gwei));
+        assert_eq!(max_fee, priority_fee);
+    }
+
+    fn revert_data(signature: &str, tokens: &[ethers::abi::Token]) -> Vec<u8> {
+        [
+            ethers::utils::id(signature).to_vec(),
+            ethers::abi::encode(tokens),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn decode_revert_reason_reads_error_string() {
+        let data = revert_data(
+            "Error(string)",
+            &[ethers::abi::Token::String(
+                "UniswapV2Router: EXPIRED".to_string(),
+            )],
+        );
+        assert_eq!(
+            decode_revert_reason(&data).as_deref(),
+            Some("UniswapV2Router: EXPIRED")
+        );
+    }
+
+    #[test]
+    fn decode_revert_reason_names_panic_codes() {
+        let data = revert_data(
+            "Panic(uint256)",
+            &[ethers::abi::Token::Uint(U256::from(0x11))],
+        );
+        assert_eq!(
+            decode_revert_reason(&data).as_deref(),
+            Some("Panic(0x11): arithmetic overflow or underflow")
+        );
+    }
+
+    #[test]
+    fn decode_revert_reason_decodes_bundled_custom_errors() {
+        let sender = CHECKSUMMED.parse::<Address>().unwrap();
+        let data = revert_data(
+            "ERC20InsufficientBalance(address,uint256,uint256)",
+            &[
+                ethers::abi::Token::Address(sender),
+                ethers::abi::Token::Uint(U256::from(5)),
+                ethers::abi::Token::Uint(U256::from(10)),
+            ],
+        );
+        let reason = decode_revert_reason(&data).unwrap();
+        assert!(reason.starts_with("ERC20InsufficientBalance("));
+        assert!(reason.ends_with(", 5, a)"));
+    }
+
+    #[test]
+    fn decode_revert_reason_ignores_unknown_and_short_data() {
+        let unknown = revert_data(
+            "NotDeclaredAnywhere(uint256)",
+            &[ethers::abi::Token::Uint(U256::one())],
+        );
+        assert_eq!(decode_revert_reason(&unknown), None);
+        assert_eq!(decode_revert_reason(&[0x08, 0xc3, 0x79]), None);
+        assert_eq!(decode_revert_reason(&[]), None);
+        let truncated = ethers::utils::id("Error(string)").to_vec();
+        assert_eq!(decode_revert_reason(&truncated), None);
+    }
+
+    fn calldata(abi: &str, function: &str, arguments: &[ethers::abi::Token]) -> Vec<u8> {
+        let abi: Abi = serde_json::from_str(abi).unwrap();
+        abi.function(function)
+            .unwrap()
+            .encode_input(arguments)
+            .unwrap()
+    }
+
+    fn selector(data: &[u8]) -> [u8; 4] {
+        data[..4].try_into().unwrap()
+    }
+
+    fn router() -> Address {
+        CHECKSUMMED.parse::<Address>().unwrap()
+    }
+
+    #[test]
+    fn router_operation_accepts_only_decodable_swaps() {
+        let path = ethers::abi::Token::Array(vec![
+            ethers::abi::Token::Address(Address::repeat_byte(1)),
+            ethers::abi::Token::Address(Address::repeat_byte(2)),
+        ]);
+        let swap = calldata(
+            CT_ROUTER02,
+            "swapExactTokensForTokens",
+            &[
+                ethers::abi::Token::Uint(U256::from(1_000)),
+                ethers::abi::Token::Uint(U256::from(900)),
+                path,
+                ethers::abi::Token::Address(Address::repeat_byte(3)),
+                ethers::abi::Token::Uint(U256::from(1_700_000_000u64)),
+            ],
+        );
+        assert!(matches!(
+            router_operation(selector(&swap), &swap[4..]),
+            std::result::Result::Ok(SignedOperation::Swap)
+        ));
+        assert!(router_operation(selector(&swap), &[]).is_err());
+
+        let remove = calldata(
+            CT_ROUTER02,
+            "removeLiquidity",
+            &[
+                ethers::abi::Token::Address(Address::repeat_byte(1)),
+                ethers::abi::Token::Address(Address::repeat_byte(2)),
+                ethers::abi::Token::Uint(U256::from(10)),
+                ethers::abi::Token::Uint(U256::zero()),
+                ethers::abi::Token::Uint(U256::zero()),
+                ethers::abi::Token::Address(Address::repeat_byte(3)),
+                ethers::abi::Token::Uint(U256::from(1_700_000_000u64)),
+            ],
+        );
+        assert!(router_operation(selector(&remove), &remove[4..]).is_err());
+        assert!(router_operation([0xde, 0xad, 0xbe, 0xef], &[]).is_err());
+    }
+
+    #[test]
+    fn token_operation_accepts_transfers_and_router_approvals() {
+        let transfer = calldata(
+            CT_ERC20,
+            "transfer",
+            &[
+                ethers::abi::Token::Address(Address::repeat_byte(4)),
+                ethers::abi::Token::Uint(U256::from(10)),
+            ],
+        );
+        assert!(matches!(
+            token_operation(selector(&transfer), &transfer[4..], router()),
+            std::result::Result::Ok(SignedOperation::Transfer)
+        ));
+
+        let approve = calldata(
+            CT_ERC20,
+            "approve",
+            &[
+                ethers::abi::Token::Address(router()),
+                ethers::abi::Token::Uint(U256::MAX),
+            ],
+        );
+        assert!(matches!(
+            token_operation(selector(&approve), &approve[4..], router()),
+            std::result::Result::Ok(SignedOperation::Approve)
+        ));
+    }
+
+    #[test]
+    fn token_operation_rejects_other_spenders_and_selectors() {
+        let approve = calldata(
+            CT_ERC20,
+            "approve",
+            &[
+                ethers::abi::Token::Address(Address::repeat_byte(5)),
+                ethers::abi::Token::Uint(U256::MAX),
+            ],
+        );
+        assert!(token_operation(selector(&approve), &approve[4..], router()).is_err());
+
+        let transfer_from = calldata(
+            CT_ERC20,
+            "transferFrom",
+            &[
+                ethers::abi::Token::Address(Address::repeat_byte(4)),
+                ethers::abi::Token::Address(Address::repeat_byte(5)),
+                ethers::abi::Token::Uint(U256::from(10)),
+            ],
+        );
+        assert!(token_operation(selector(&transfer_from), &transfer_from[4..], router()).is_err());
+        assert!(token_operation([0xde, 0xad, 0xbe, 0xef], &[], router()).is_err());
     }
 }
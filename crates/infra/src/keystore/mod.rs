@@ -0,0 +1,84 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use anyhow::Result;
+use domain::shared::error::DomainError;
+use serde::{Deserialize, Serialize};
+use std::env;
+
+//what is stored in the keystore column, the wallet address is bound as associated data
+#[derive(Debug, Deserialize, Serialize)]
+pub struct EncryptedKey {
+    pub version: u8,
+    pub cipher: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+//encrypts private keys with the master key taken from the .env file
+pub struct KeystoreCipher {
+    cipher: Aes256Gcm,
+}
+
+impl KeystoreCipher {
+    pub fn new() -> Self {
+        let master_key = env::var("KEYSTORE_MASTER_KEY").expect("Keystore master key must be set");
+        let key_bytes = hex::decode(master_key.trim_start_matches("0x"))
+            .expect("Keystore master key must be hex encoded");
+        assert!(
+            key_bytes.len() == 32,
+            "Keystore master key must be 32 bytes long"
+        );
+        let key = Key::<Aes256Gcm>::from_slice(&key_bytes);
+        Self {
+            cipher: Aes256Gcm::new(key),
+        }
+    }
+
+    pub fn encrypt(&self, address: &str, private_key: &[u8]) -> Result<String> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let aad = address.to_lowercase();
+        let payload = Payload {
+            msg: private_key,
+            aad: aad.as_bytes(),
+        };
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, payload)
+            .map_err(|_| DomainError::Internal("Failed to encrypt wallet key".to_string()))?;
+
+        let encrypted = EncryptedKey {
+            version: 1,
+            cipher: "aes-256-gcm".to_string(),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        };
+        Ok(serde_json::to_string(&encrypted)?)
+    }
+
+    pub fn decrypt(&self, address: &str, keystore: &str) -> Result<Vec<u8>> {
+        let encrypted: EncryptedKey = serde_json::from_str(keystore)?;
+        let nonce = hex::decode(&encrypted.nonce)?;
+        if nonce.len() != 12 {
+            return Err(DomainError::Internal("Corrupted wallet keystore".to_string()).into());
+        }
+        let ciphertext = hex::decode(&encrypted.ciphertext)?;
+        let aad = address.to_lowercase();
+        let payload = Payload {
+            msg: ciphertext.as_ref(),
+            aad: aad.as_bytes(),
+        };
+        let private_key = self
+            .cipher
+            .decrypt(Nonce::from_slice(&nonce), payload)
+            .map_err(|_| DomainError::Internal("Failed to decrypt wallet key".to_string()))?;
+        Ok(private_key)
+    }
+}
+
+impl Default for KeystoreCipher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
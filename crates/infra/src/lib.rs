@@ -1,5 +1,8 @@
 pub mod celestia_repository;
 pub mod circle_repository;
+pub mod config;
 pub mod contract_abi;
 pub mod infura_repository;
+pub mod keystore;
+pub mod nonce_manager;
 pub mod postgres_repository;
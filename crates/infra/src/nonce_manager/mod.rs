@@ -0,0 +1,208 @@
+use async_trait::async_trait;
+use domain::repository::nonce_repository::NonceRepository;
+use ethers::{
+    providers::{Middleware, MiddlewareError, PendingTransaction},
+    types::{transaction::eip2718::TypedTransaction, Address, BlockId, BlockNumber, H256, U256},
+    utils::keccak256,
+};
+use std::{
+    error::Error,
+    fmt::{self, Debug, Display},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+//signer layer taking every nonce from the shared nonce store instead of the node's pending count
+//lambdas running side by side for the same wallet would otherwise send the same nonce twice
+//in dry run transactions are filled and signed the same way but never broadcast
+pub struct SharedNonceMiddleware<M> {
+    inner: M,
+    address: Address,
+    nonces: Arc<dyn NonceRepository>,
+    dry_run: bool,
+    //transactions signed so far in dry run, they take the nonces after the pending count
+    dry_run_signed: AtomicU64,
+}
+
+impl<M: Middleware> SharedNonceMiddleware<M> {
+    pub fn new(
+        inner: M,
+        address: Address,
+        nonces: Arc<dyn NonceRepository>,
+        dry_run: bool,
+    ) -> Self {
+        Self {
+            inner,
+            address,
+            nonces,
+            dry_run,
+            dry_run_signed: AtomicU64::new(0),
+        }
+    }
+
+    //address the nonces are managed for
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    //reserve the next nonce, the pending count of the node is only its lower bound
+    async fn allocate_nonce(&self) -> Result<U256, SharedNonceError<M>> {
+        let chain_nonce = self
+            .inner
+            .get_transaction_count(self.address, Some(BlockNumber::Pending.into()))
+            .await
+            .map_err(SharedNonceError::MiddlewareError)?;
+        let nonce = self
+            .nonces
+            .allocate_nonce(&format!("{:?}", self.address), chain_nonce.as_u64())
+            .await
+            .map_err(|e| SharedNonceError::NonceError(e.to_string()))?;
+        Ok(U256::from(nonce))
+    }
+
+    //fill and sign the transaction, then hand back the hash it would have on chain
+    //the shared nonces are left untouched since nothing reaches the node
+    async fn sign_without_broadcast(
+        &self,
+        mut tx: TypedTransaction,
+        block: Option<BlockId>,
+    ) -> Result<PendingTransaction<'_, M::Provider>, SharedNonceError<M>> {
+        if tx.nonce().is_none() {
+            let chain_nonce = self
+                .inner
+                .get_transaction_count(self.address, Some(BlockNumber::Pending.into()))
+                .await
+                .map_err(SharedNonceError::MiddlewareError)?;
+            let signed = self.dry_run_signed.fetch_add(1, Ordering::SeqCst);
+            tx.set_nonce(chain_nonce + signed);
+        }
+        self.inner
+            .fill_transaction(&mut tx, block)
+            .await
+            .map_err(SharedNonceError::MiddlewareError)?;
+        let signature = self
+            .inner
+            .sign_transaction(&tx, self.address)
+            .await
+            .map_err(SharedNonceError::MiddlewareError)?;
+        let tx_hash = H256::from(keccak256(tx.rlp_signed(&signature)));
+
+        //the node never sees the hash, so waiting on it ends after one lookup without a receipt
+        Ok(PendingTransaction::new(tx_hash, self.inner.provider())
+            .interval(Duration::from_secs(1))
+            .retries(0))
+    }
+}
+
+impl<M: Debug> Debug for SharedNonceMiddleware<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedNonceMiddleware")
+            .field("inner", &self.inner)
+            .field("address", &self.address)
+            .field("dry_run", &self.dry_run)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl<M: Middleware> Middleware for SharedNonceMiddleware<M> {
+    type Error = SharedNonceError<M>;
+    type Provider = M::Provider;
+    type Inner = M;
+
+    fn inner(&self) -> &M {
+        &self.inner
+    }
+
+    //transactions given an explicit nonce are sent as they are
+    async fn send_transaction<T: Into<TypedTransaction> + Send + Sync>(
+        &self,
+        tx: T,
+        block: Option<BlockId>,
+    ) -> Result<PendingTransaction<'_, Self::Provider>, Self::Error> {
+        let mut tx = tx.into();
+        if self.dry_run {
+            return self.sign_without_broadcast(tx, block).await;
+        }
+        let allocated = match tx.nonce() {
+            Some(_) => None,
+            None => {
+                let nonce = self.allocate_nonce().await?;
+                tx.set_nonce(nonce);
+                Some(nonce)
+            }
+        };
+
+        match self.inner.send_transaction(tx, block).await {
+            Ok(pending_tx) => Ok(pending_tx),
+            Err(error) => {
+                //the node refused it, so the nonce is free again for the next transaction
+                if let Some(nonce) = allocated {
+                    if let Err(release_error) = self
+                        .nonces
+                        .release_nonce(&format!("{:?}", self.address), nonce.as_u64())
+                        .await
+                    {
+                        tracing::error!(
+                            "failed to release nonce {} of {:?} : {:?}",
+                            nonce,
+                            self.address,
+                            release_error
+                        );
+                    }
+                }
+                Err(SharedNonceError::MiddlewareError(error))
+            }
+        }
+    }
+}
+
+pub enum SharedNonceError<M: Middleware> {
+    //the nonce store could not hand out a nonce
+    NonceError(String),
+    MiddlewareError(M::Error),
+}
+
+impl<M: Middleware> Display for SharedNonceError<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SharedNonceError::NonceError(message) => {
+                write!(f, "failed to allocate nonce: {}", message)
+            }
+            SharedNonceError::MiddlewareError(error) => Display::fmt(error, f),
+        }
+    }
+}
+
+impl<M: Middleware> Debug for SharedNonceError<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SharedNonceError::NonceError(message) => {
+                f.debug_tuple("NonceError").field(message).finish()
+            }
+            SharedNonceError::MiddlewareError(error) => {
+                f.debug_tuple("MiddlewareError").field(error).finish()
+            }
+        }
+    }
+}
+
+impl<M: Middleware> Error for SharedNonceError<M> {}
+
+impl<M: Middleware> MiddlewareError for SharedNonceError<M> {
+    type Inner = M::Error;
+
+    fn from_err(src: M::Error) -> Self {
+        SharedNonceError::MiddlewareError(src)
+    }
+
+    fn as_inner(&self) -> Option<&Self::Inner> {
+        match self {
+            SharedNonceError::MiddlewareError(error) => Some(error),
+            _ => None,
+        }
+    }
+}
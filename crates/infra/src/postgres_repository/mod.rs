@@ -1,6 +1,13 @@
 use anyhow::{Ok, Result};
 use async_trait::async_trait;
-use domain::{entity::W3Transaction, repository::database_repository::DatabaseRepository};
+use domain::{
+    entity::{W3Token, W3Transaction, W3Wallet},
+    repository::{
+        database_repository::DatabaseRepository, keystore_repository::KeystoreRepository,
+        nonce_repository::NonceRepository, token_repository::TokenRepository,
+    },
+    shared::error::DomainError,
+};
 use sqlx::{
     postgres::{PgArguments, PgRow},
     query::Query,
@@ -8,6 +15,19 @@ use sqlx::{
 };
 use std::env;
 
+//nonces handed out longer ago than this without showing up on the node were never broadcast
+const NONCE_GAP_SECONDS: u64 = 60;
+
+//nonce to hand out from the node's pending count and the one the service stored
+//the node is ahead when transactions were sent outside of the service
+//a stale lead over the node is a gap left by transactions that were never broadcast
+fn resolve_nonce(chain_nonce: i64, next_nonce: i64, stale: bool) -> i64 {
+    match (chain_nonce > next_nonce, next_nonce > chain_nonce && stale) {
+        (false, false) => next_nonce,
+        _ => chain_nonce,
+    }
+}
+
 pub struct PostgresRepository {
     pub pool: PgPool,
 }
@@ -31,7 +51,7 @@ impl PostgresRepository {
     }
 
     async fn raw_update(&self, query: Query<'_, Postgres, PgArguments>) -> Result<()> {
-        let rs = query.execute(&self.pool).await?;
+        query.execute(&self.pool).await?;
         Ok(())
     }
 }
@@ -40,19 +60,211 @@ impl PostgresRepository {
 impl DatabaseRepository for PostgresRepository {
     //get transaction of user over an address
     async fn get_related_transaction(&self, address: &str) -> Result<Vec<W3Transaction>> {
-        let query_str =
-            format!("select w3_address, w3_height from w3_transaction where w3_address = $1");
-        let query_builder = query_as::<_, W3Transaction>(&query_str).bind(address);
+        let query_str = "select w3_address, w3_height, tx_hash, tx_status, replaced_by, extract(epoch from now() - created_at)::bigint as age_seconds from w3_transaction where w3_address = $1";
+        let query_builder = query_as::<_, W3Transaction>(query_str).bind(address);
         let rs = self.raw_query(query_builder).await?;
         Ok(rs)
     }
 
     //update new transaction of user
-    async fn insert_new_transaction(&self, height: &str, address: &str) -> Result<()> {
+    async fn insert_new_transaction(
+        &self,
+        height: &str,
+        address: &str,
+        tx_hash: Option<&str>,
+        tx_status: Option<&str>,
+    ) -> Result<()> {
+        let query_str = "insert into w3_transaction (w3_height, w3_address, tx_hash, tx_status) values ($1, $2, $3, $4)";
+        let query_buidler = sqlx::query(query_str)
+            .bind(height)
+            .bind(address)
+            .bind(tx_hash.map(str::to_lowercase))
+            .bind(tx_status);
+        self.raw_update(query_buidler).await?;
+        Ok(())
+    }
+
+    //get the history record of an on-chain transaction
+    async fn get_transaction_by_hash(&self, tx_hash: &str) -> Result<Option<W3Transaction>> {
         let query_str =
-            format!("insert into w3_transaction (w3_height, w3_address) values ($1, $2)");
-        let query_buidler = sqlx::query(&query_str).bind(height).bind(address);
-        let rs = self.raw_update(query_buidler).await?;
+            "select w3_address, w3_height, tx_hash, tx_status, replaced_by, extract(epoch from now() - created_at)::bigint as age_seconds from w3_transaction where tx_hash = $1";
+        let query_builder = query_as::<_, W3Transaction>(query_str).bind(tx_hash.to_lowercase());
+        let rs = self.raw_query(query_builder).await?;
+        Ok(rs.into_iter().next())
+    }
+
+    //keep the last status seen on chain
+    async fn update_transaction_status(&self, tx_hash: &str, tx_status: &str) -> Result<()> {
+        let query_str = "update w3_transaction set tx_status = $1 where tx_hash = $2";
+        let query_builder = sqlx::query(query_str)
+            .bind(tx_status)
+            .bind(tx_hash.to_lowercase());
+        self.raw_update(query_builder).await?;
         Ok(())
     }
+
+    //point the original transaction at the one sent to replace it
+    async fn link_replacement(&self, tx_hash: &str, replaced_by: &str) -> Result<()> {
+        let query_str = "update w3_transaction set replaced_by = $1 where tx_hash = $2";
+        let query_builder = sqlx::query(query_str)
+            .bind(replaced_by.to_lowercase())
+            .bind(tx_hash.to_lowercase());
+        self.raw_update(query_builder).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl KeystoreRepository for PostgresRepository {
+    //store a new custodial wallet with its encrypted key
+    async fn insert_wallet(&self, wallet: &W3Wallet) -> Result<()> {
+        let query_str =
+            "insert into w3_wallet (wallet_id, w3_address, w3_keystore) values ($1, $2, $3)";
+        let query_builder = sqlx::query(query_str)
+            .bind(&wallet.wallet_id)
+            .bind(&wallet.w3_address)
+            .bind(&wallet.w3_keystore);
+        self.raw_update(query_builder).await?;
+        Ok(())
+    }
+
+    //get a custodial wallet by its id
+    async fn get_wallet(&self, wallet_id: &str) -> Result<W3Wallet> {
+        let query_str =
+            "select wallet_id, w3_address, w3_keystore from w3_wallet where wallet_id = $1";
+        let query_builder = query_as::<_, W3Wallet>(query_str).bind(wallet_id);
+        let rs = self.raw_query(query_builder).await?;
+        let wallet = rs
+            .into_iter()
+            .next()
+            .ok_or_else(|| DomainError::NotFound(format!("Wallet {} not found", wallet_id)))?;
+        Ok(wallet)
+    }
+}
+
+#[async_trait]
+impl TokenRepository for PostgresRepository {
+    //get every registered token
+    async fn list_tokens(&self) -> Result<Vec<W3Token>> {
+        let query_str =
+            "select symbol, address, decimals, network, kind, fee_on_transfer, transfer_fee_bps from w3_token order by network, symbol";
+        let query_builder = query_as::<_, W3Token>(query_str);
+        let rs = self.raw_query(query_builder).await?;
+        Ok(rs)
+    }
+
+    //get a token by its symbol on a network
+    async fn get_token(&self, symbol: &str, network: &str) -> Result<Option<W3Token>> {
+        let query_str = "select symbol, address, decimals, network, kind, fee_on_transfer, transfer_fee_bps from w3_token where symbol = $1 and network = $2";
+        let query_builder = query_as::<_, W3Token>(query_str)
+            .bind(symbol.to_uppercase())
+            .bind(network.to_lowercase());
+        let rs = self.raw_query(query_builder).await?;
+        Ok(rs.into_iter().next())
+    }
+
+    //register a new token
+    async fn insert_token(&self, token: &W3Token) -> Result<()> {
+        let query_str = "insert into w3_token (symbol, address, decimals, network, kind, fee_on_transfer, transfer_fee_bps) values ($1, $2, $3, $4, $5, $6, $7)";
+        let query_builder = sqlx::query(query_str)
+            .bind(&token.symbol)
+            .bind(&token.address)
+            .bind(token.decimals)
+            .bind(&token.network)
+            .bind(&token.kind)
+            .bind(token.fee_on_transfer)
+            .bind(token.transfer_fee_bps);
+        self.raw_update(query_builder).await?;
+        Ok(())
+    }
+
+    //keep the fee measured on the last swap of a taxed token
+    async fn update_transfer_fee(&self, symbol: &str, network: &str, fee_bps: i32) -> Result<()> {
+        let query_str =
+            "update w3_token set transfer_fee_bps = $1 where symbol = $2 and network = $3";
+        let query_builder = sqlx::query(query_str)
+            .bind(fee_bps)
+            .bind(symbol.to_uppercase())
+            .bind(network.to_lowercase());
+        self.raw_update(query_builder).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl NonceRepository for PostgresRepository {
+    //the row of the address stays locked until the nonce is taken, so concurrent lambdas queue up on it
+    async fn allocate_nonce(&self, address: &str, chain_nonce: u64) -> Result<u64> {
+        let address = address.to_lowercase();
+        let chain_nonce = chain_nonce as i64;
+        let mut transaction = self.pool.begin().await?;
+
+        let insert_str = "insert into w3_nonce (w3_address, next_nonce) values ($1, $2) on conflict (w3_address) do nothing";
+        sqlx::query(insert_str)
+            .bind(&address)
+            .bind(chain_nonce)
+            .execute(&mut *transaction)
+            .await?;
+
+        let select_str = format!(
+            "select next_nonce, updated_at < now() - interval '{} seconds' from w3_nonce where w3_address = $1 for update",
+            NONCE_GAP_SECONDS
+        );
+        let (next_nonce, stale) = query_as::<_, (i64, bool)>(&select_str)
+            .bind(&address)
+            .fetch_one(&mut *transaction)
+            .await?;
+
+        let nonce = resolve_nonce(chain_nonce, next_nonce, stale);
+
+        let update_str =
+            "update w3_nonce set next_nonce = $2, updated_at = now() where w3_address = $1";
+        sqlx::query(update_str)
+            .bind(&address)
+            .bind(nonce + 1)
+            .execute(&mut *transaction)
+            .await?;
+        transaction.commit().await?;
+        Ok(nonce as u64)
+    }
+
+    //only the last nonce handed out can be taken back, older gaps are left to the resync
+    async fn release_nonce(&self, address: &str, nonce: u64) -> Result<()> {
+        let query_str =
+            "update w3_nonce set next_nonce = $2 where w3_address = $1 and next_nonce = $3";
+        let query_builder = sqlx::query(query_str)
+            .bind(address.to_lowercase())
+            .bind(nonce as i64)
+            .bind(nonce as i64 + 1);
+        self.raw_update(query_builder).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_nonce_follows_a_node_ahead_of_the_service() {
+        assert_eq!(resolve_nonce(12, 10, false), 12);
+        assert_eq!(resolve_nonce(12, 10, true), 12);
+    }
+
+    #[test]
+    fn resolve_nonce_resyncs_a_stale_lead() {
+        assert_eq!(resolve_nonce(10, 13, true), 10);
+    }
+
+    #[test]
+    fn resolve_nonce_keeps_a_fresh_lead() {
+        //transactions handed out within NONCE_GAP_SECONDS may not have reached the node yet
+        assert_eq!(resolve_nonce(10, 13, false), 13);
+    }
+
+    #[test]
+    fn resolve_nonce_keeps_the_stored_nonce_when_in_sync() {
+        assert_eq!(resolve_nonce(10, 10, false), 10);
+        assert_eq!(resolve_nonce(10, 10, true), 10);
+    }
 }
@@ -1,11 +1,35 @@
 use dotenv::dotenv;
-use framework::handler::lambda_handler;
+use framework::{handler::lambda_handler, server::build_rocket, state::ServiceState};
 use lambda_http::{aws_lambda_events::encodings::Error, run, service_fn};
 use rocket::tokio;
+use std::{env, sync::Arc};
 
 #[tokio::main]
 pub async fn main() -> Result<(), Error> {
     //load .env file
     dotenv().ok();
-    run(service_fn(lambda_handler)).await
+
+    //log level follows AWS_LAMBDA_LOG_LEVEL or RUST_LOG, INFO by default
+    lambda_http::tracing::init_default_subscriber();
+
+    //repositories are built once here and shared by every request
+    let state = Arc::new(ServiceState::from_env().await);
+
+    //RUN_MODE=server starts a local rocket server, anything else keeps the lambda runtime
+    match env::var("RUN_MODE").unwrap_or_default().as_str() {
+        "server" => {
+            build_rocket(state)
+                .launch()
+                .await
+                .map_err(|err| Error::from(err.to_string()))?;
+            Ok(())
+        }
+        _ => {
+            run(service_fn(move |event| {
+                let state = state.clone();
+                async move { lambda_handler(&state, event).await }
+            }))
+            .await
+        }
+    }
 }